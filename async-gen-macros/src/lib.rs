@@ -2,6 +2,21 @@ use proc_macro::*;
 
 #[proc_macro]
 pub fn gen_inner(input: TokenStream) -> TokenStream {
+    expand(input, Kind::Gen)
+}
+
+#[proc_macro]
+pub fn local_gen_inner(input: TokenStream) -> TokenStream {
+    expand(input, Kind::Local)
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Gen,
+    Local,
+}
+
+fn expand(input: TokenStream, kind: Kind) -> TokenStream {
     let mut tokens = input.into_iter();
 
     let Some(TokenTree::Group(crate_path)) = tokens.next() else {
@@ -9,24 +24,32 @@ pub fn gen_inner(input: TokenStream) -> TokenStream {
     };
     let crate_path = crate_path.stream();
 
-    let mut has_yielded = false;
-    let output = out(tokens, &mut has_yielded);
+    let mut state = State::default();
+    let output = out(tokens, &mut state);
 
     let mut o = TokenStream::new();
     o.extend(crate_path.clone());
     o.push_colon2();
-    o.push_ident("gen");
+    o.push_ident(match (kind, state.has_throw) {
+        (Kind::Gen, false) => "gen",
+        (Kind::Gen, true) => "gen_throwable",
+        (Kind::Local, false) => "local_gen",
+        (Kind::Local, true) => "local_gen_throwable",
+    });
 
     o.push_group(Delimiter::Parenthesis, |o| {
         o.push_punct('|');
         o.push_ident("mut");
         o.push_ident("yield_");
 
-        if !has_yielded {
+        if !state.has_yielded {
             o.push_punct(':');
             o.extend(crate_path);
             o.push_colon2();
-            o.push_ident("Yield");
+            o.push_ident(match kind {
+                Kind::Gen => "Yield",
+                Kind::Local => "LocalYield",
+            });
         }
 
         o.push_punct('|');
@@ -54,13 +77,28 @@ pub fn gen_inner(input: TokenStream) -> TokenStream {
     o
 }
 
-fn out(mut tokens: token_stream::IntoIter, has_yielded: &mut bool) -> Group {
+#[derive(Default)]
+struct State {
+    has_yielded: bool,
+    /// `yield?` was used, so errors can be thrown into the generator.
+    has_throw: bool,
+}
+
+fn out(tokens: token_stream::IntoIter, state: &mut State) -> Group {
     let mut o = TokenStream::new();
+    let mut tokens = tokens.peekable();
 
     while let Some(tt) = tokens.next() {
         match tt {
             TokenTree::Ident(name) if name.to_string() == "yield" => {
-                *has_yielded = true;
+                state.has_yielded = true;
+                let span = name.span();
+                let is_try =
+                    matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '?');
+                if is_try {
+                    state.has_throw = true;
+                    tokens.next();
+                }
                 let mut expr = TokenStream::new();
                 for tt in &mut tokens {
                     match tt {
@@ -71,16 +109,44 @@ fn out(mut tokens: token_stream::IntoIter, has_yielded: &mut bool) -> Group {
                 if expr.is_empty() {
                     expr.push(Group::new(Delimiter::Parenthesis, TokenStream::new()));
                 };
+                // `yield? value;` expands to `match yield_.yield_try(value).await { Ok(()) => {} Err(err) => return Err(err) }`,
+                // without `From` conversion (unlike `?`), so the error type is inferred from the body.
+                if is_try {
+                    o.push_ident("match");
+                }
                 o.push_ident("yield_");
                 o.push_punct('.');
-                o.push_ident("yield_");
-                o.push(Group::new(Delimiter::Parenthesis, expr));
-                o.push_punct('.');
-                o.push_ident("await");
+                // Method call is spanned to `yield` keyword, so that `#[track_caller]`
+                // on `Yield::yield_` records the location of this `yield`.
+                let mut call = TokenStream::new();
+                call.push_ident(if is_try { "yield_try" } else { "yield_" });
+                call.push(Group::new(Delimiter::Parenthesis, expr));
+                call.push_punct('.');
+                call.push_ident("await");
+                o.extend(call.into_iter().map(|mut tt| {
+                    tt.set_span(span);
+                    tt
+                }));
+                if is_try {
+                    o.push_group(Delimiter::Brace, |o| {
+                        o.push_ident("Ok");
+                        o.push_group(Delimiter::Parenthesis, |o| {
+                            o.push_group(Delimiter::Parenthesis, |_| {})
+                        });
+                        o.push_fat_arrow();
+                        o.push_group(Delimiter::Brace, |_| {});
+                        o.push_ident("Err");
+                        o.push_group(Delimiter::Parenthesis, |o| o.push_ident("err"));
+                        o.push_fat_arrow();
+                        o.push_ident("return");
+                        o.push_ident("Err");
+                        o.push_group(Delimiter::Parenthesis, |o| o.push_ident("err"));
+                    });
+                }
                 o.push_punct(';');
             }
             TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
-                o.push(out(g.stream().into_iter(), has_yielded));
+                o.push(out(g.stream().into_iter(), state));
             }
             _ => o.push(tt),
         }
@@ -110,6 +176,12 @@ trait TokenStreamExt {
         self.push(Group::new(delimiter, stream))
     }
 
+    #[inline]
+    fn push_fat_arrow(&mut self) {
+        self.push(Punct::new('=', Spacing::Joint));
+        self.push(Punct::new('>', Spacing::Alone));
+    }
+
     #[inline]
     fn push_colon2(&mut self) {
         self.push(Punct::new(':', Spacing::Joint));
@@ -0,0 +1,68 @@
+use async_gen::{gen, AsyncGen, Return};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures_util::task::noop_waker_ref;
+use std::{
+    future::Future,
+    hint::black_box,
+    pin::pin,
+    task::{Context, Poll},
+};
+
+const ITEMS: usize = 10_000;
+
+fn single(n: usize) -> AsyncGen<impl Future<Output = Return>, usize> {
+    gen(move |mut c| async move {
+        for i in 0..n {
+            c.yield_(i).await;
+        }
+        c.return_(())
+    })
+}
+
+fn many(n: usize, chunk: usize) -> AsyncGen<impl Future<Output = Return>, usize> {
+    gen(move |mut c| async move {
+        for start in (0..n).step_by(chunk) {
+            c.yield_many(start..(start + chunk).min(n)).await;
+        }
+        c.return_(())
+    })
+}
+
+fn handoff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("handoff");
+    group.throughput(Throughput::Elements(ITEMS as u64));
+
+    group.bench_function("poll_next", |b| {
+        b.iter(|| {
+            let mut cx = Context::from_waker(noop_waker_ref());
+            let mut g = pin!(single(ITEMS));
+            while let Poll::Ready(Some(v)) = g.as_mut().poll_next(&mut cx) {
+                black_box(v);
+            }
+        })
+    });
+
+    for chunk in [16, 256] {
+        group.bench_with_input(
+            BenchmarkId::new("poll_next_batch", chunk),
+            &chunk,
+            |b, &chunk| {
+                let mut buf = Vec::with_capacity(chunk);
+                b.iter(|| {
+                    let mut cx = Context::from_waker(noop_waker_ref());
+                    let mut g = pin!(many(ITEMS, chunk));
+                    while let Poll::Ready(Some(_)) =
+                        g.as_mut().poll_next_batch(&mut cx, &mut buf, chunk)
+                    {
+                        black_box(&buf);
+                        buf.clear();
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, handoff);
+criterion_main!(benches);
@@ -0,0 +1,145 @@
+use crate::{AsyncGen, AsyncGenerator, GeneratorState, Return};
+use std::{
+    fmt,
+    future::{poll_fn, Future},
+    pin::{pin, Pin},
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run the future to completion, parking the current thread while it's pending.
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(val) => return val,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+impl<Fut, Y, E, R> AsyncGen<Fut, Y, E>
+where
+    Fut: Future<Output = Return<R>>,
+{
+    /// Resume the generator, and block the current thread until it yields or completes.
+    ///
+    /// The thread is parked while the generator is pending, so no async runtime is needed.
+    /// But the generator body must not depend on a runtime either, for example tokio's
+    /// timers or I/O resources.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::pin::pin;
+    /// use async_gen::{gen, GeneratorState};
+    ///
+    /// let mut g = pin!(gen! {
+    ///     yield 42;
+    ///     return "42";
+    /// });
+    /// assert_eq!(g.resume_blocking(), GeneratorState::Yielded(42));
+    /// assert_eq!(g.resume_blocking(), GeneratorState::Complete("42"));
+    /// ```
+    pub fn resume_blocking(self: &mut Pin<&mut Self>) -> GeneratorState<Y, R> {
+        block_on(poll_fn(|cx| self.as_mut().poll_resume(cx)))
+    }
+
+    /// Creates a blocking iterator over the yielded values.
+    ///
+    /// See [`BlockingIter`] for more details.
+    #[inline]
+    pub fn into_blocking_iter(self) -> BlockingIter<Self> {
+        BlockingIter::new(self)
+    }
+}
+
+/// A blocking iterator over the values yielded by an underlying generator.
+///
+/// The current thread is parked while the generator is pending (See [`AsyncGen::resume_blocking`]).
+/// Once the iterator is exhausted, the return value of the generator is available.
+///
+/// ## Example
+///
+/// ```
+/// use async_gen::gen;
+///
+/// let mut it = gen! {
+///     yield 1;
+///     yield 2;
+///     return "done";
+/// }
+/// .into_blocking_iter();
+///
+/// assert_eq!(it.by_ref().collect::<Vec<_>>(), [1, 2]);
+/// assert_eq!(it.into_return(), Some("done"));
+/// ```
+pub struct BlockingIter<G: AsyncGenerator> {
+    gen: Pin<Box<G>>,
+    ret: Option<G::Return>,
+    done: bool,
+}
+
+impl<G: AsyncGenerator> BlockingIter<G> {
+    /// Creates a blocking iterator from the generator.
+    pub fn new(gen: G) -> Self {
+        Self {
+            gen: Box::pin(gen),
+            ret: None,
+            done: false,
+        }
+    }
+
+    /// Returns the return value of the generator, if it has completed.
+    #[inline]
+    pub fn return_value(&self) -> Option<&G::Return> {
+        self.ret.as_ref()
+    }
+
+    /// Consumes the iterator, and returns the return value of the generator, if it has completed.
+    #[inline]
+    pub fn into_return(self) -> Option<G::Return> {
+        self.ret
+    }
+}
+
+impl<G: AsyncGenerator> Iterator for BlockingIter<G> {
+    type Item = G::Yield;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match block_on(poll_fn(|cx| self.gen.as_mut().poll_resume(cx))) {
+            GeneratorState::Yielded(val) => Some(val),
+            GeneratorState::Complete(val) => {
+                self.ret = Some(val);
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+impl<G: AsyncGenerator> fmt::Debug for BlockingIter<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingIter")
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
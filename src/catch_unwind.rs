@@ -0,0 +1,138 @@
+use crate::{AsyncGen, AsyncGenerator, Flavor, GeneratorState, Return};
+use pin_project_lite::pin_project;
+use std::{
+    any::Any,
+    future::Future,
+    panic::{self, AssertUnwindSafe, UnwindSafe},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+pin_project! {
+    /// A generator wrapper, that catches a panic of the generator body.
+    ///
+    /// A panic is returned as [`GeneratorState::Complete`] with an `Err` holding the
+    /// panic payload, instead of unwinding through the caller of `poll_resume`. The
+    /// panicked generator is never polled again.
+    ///
+    /// As a [`Stream`](futures_core::Stream), the panic is yielded as the last item.
+    ///
+    /// This `struct` is created by [`AsyncGen::catch_unwind`] or [`CatchUnwind::new`].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use async_gen::{gen, BlockingIter};
+    ///
+    /// let mut it = BlockingIter::new(gen! {
+    ///     yield 1;
+    ///     panic!("boom");
+    /// }
+    /// .catch_unwind());
+    ///
+    /// assert_eq!(it.next(), Some(1));
+    /// assert_eq!(it.next(), None);
+    /// let payload = it.into_return().unwrap().unwrap_err();
+    /// assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+    /// ```
+    #[derive(Debug)]
+    pub struct CatchUnwind<G> {
+        #[pin]
+        gen: G,
+        done: bool,
+    }
+}
+
+impl<G: AsyncGenerator + UnwindSafe> CatchUnwind<G> {
+    /// Wrap the generator, to catch a panic of its body.
+    ///
+    /// The generator must be [`UnwindSafe`], wrap it with [`AssertUnwindSafe`] otherwise.
+    /// A generator created by [`gen()`](crate::gen()) never is, because its body shares
+    /// state with the generator. So the caller asserts that values captured by the body,
+    /// that outlive the panicked generator, are still usable.
+    #[inline]
+    pub fn new(gen: G) -> Self {
+        Self { gen, done: false }
+    }
+}
+
+impl<G: AsyncGenerator> AsyncGenerator for CatchUnwind<G> {
+    type Yield = G::Yield;
+    type Return = Result<G::Return, Box<dyn Any + Send>>;
+
+    /// # Panics
+    ///
+    /// Panics if the generator body has already panicked.
+    fn poll_resume(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        let me = self.project();
+        assert!(
+            !*me.done,
+            "`CatchUnwind` resumed after the generator panicked"
+        );
+        // Unwind safety is checked (or asserted) by the constructor,
+        // and the generator isn't polled again after a panic.
+        match panic::catch_unwind(AssertUnwindSafe(|| me.gen.poll_resume(cx))) {
+            Ok(Poll::Ready(GeneratorState::Yielded(val))) => {
+                Poll::Ready(GeneratorState::Yielded(val))
+            }
+            Ok(Poll::Ready(GeneratorState::Complete(val))) => {
+                Poll::Ready(GeneratorState::Complete(Ok(val)))
+            }
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => {
+                *me.done = true;
+                Poll::Ready(GeneratorState::Complete(Err(payload)))
+            }
+        }
+    }
+}
+
+impl<G: AsyncGenerator<Return = ()>> futures_core::Stream for CatchUnwind<G> {
+    type Item = Result<G::Yield, Box<dyn Any + Send>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        self.poll_resume(cx).map(|s| match s {
+            GeneratorState::Yielded(val) => Some(Ok(val)),
+            GeneratorState::Complete(Ok(())) => None,
+            GeneratorState::Complete(Err(payload)) => Some(Err(payload)),
+        })
+    }
+}
+
+impl<G: AsyncGenerator> AsyncGenerator for AssertUnwindSafe<G> {
+    type Yield = G::Yield;
+    type Return = G::Return;
+
+    #[inline]
+    fn poll_resume(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        // SEAFTY: `AssertUnwindSafe` is a transparent wrapper, `G` is never moved out of it.
+        unsafe { self.map_unchecked_mut(|g| &mut g.0) }.poll_resume(cx)
+    }
+}
+
+impl<Fut, Y, E, F, R> AsyncGen<Fut, Y, E, F>
+where
+    Fut: Future<Output = Return<R>>,
+    F: Flavor,
+{
+    /// Catch a panic of the generator body, asserting that the generator is unwind safe.
+    ///
+    /// Same as `CatchUnwind::new(AssertUnwindSafe(self))`. The body shares state with the
+    /// generator, so it's never [`UnwindSafe`]. Like [`AssertUnwindSafe`], the caller asserts
+    /// that values captured by the body, that outlive the panicked generator, are still usable.
+    ///
+    /// See [`CatchUnwind`] for more details.
+    #[inline]
+    pub fn catch_unwind(self) -> CatchUnwind<AssertUnwindSafe<Self>> {
+        CatchUnwind::new(AssertUnwindSafe(self))
+    }
+}
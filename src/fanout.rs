@@ -0,0 +1,511 @@
+use crate::{AsyncGen, AsyncGenerator, GeneratorState, Return};
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll, Wake, Waker},
+};
+
+/// What to do when the buffer of [`AsyncGen::broadcast_with`] is full.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+pub enum Overflow {
+    /// Wait for the slowest subscriber, before producing more values.
+    #[default]
+    Backpressure,
+    /// Drop the oldest value. Subscribers that haven't received it yet skip it
+    /// (See [`Subscriber::lagged`]).
+    DropOldest,
+}
+
+/// Wakers of the handles waiting for a value.
+///
+/// The generator is polled with this waker, so whichever handle polls next drives it.
+#[derive(Default)]
+struct Waiters(Mutex<Vec<Waker>>);
+
+impl Waiters {
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+
+    fn wake_all(&self) {
+        let wakers = mem::take(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner));
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+impl Wake for Waiters {
+    fn wake(self: Arc<Self>) {
+        self.wake_all();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.wake_all();
+    }
+}
+
+struct State<G: AsyncGenerator> {
+    /// `None` once the generator has completed or panicked, or while it is being polled.
+    gen: Option<Pin<Box<G>>>,
+    /// Set while a handle polls the generator, without holding the lock.
+    polling: bool,
+    /// Values, that are not received by every handle yet.
+    items: VecDeque<G::Yield>,
+    /// Index of `items[0]`.
+    base: u64,
+    ret: Option<Arc<G::Return>>,
+    /// Cursor of each handle, indexed by handle id.
+    cursors: Vec<Option<u64>>,
+    /// Maximum number of `items`, or `None` to keep every value.
+    capacity: Option<usize>,
+    overflow: Overflow,
+}
+
+impl<G: AsyncGenerator> State<G> {
+    fn end(&self) -> u64 {
+        self.base + self.items.len() as u64
+    }
+
+    /// Drop the values that every handle has received.
+    fn trim(&mut self) -> bool {
+        if self.capacity.is_none() {
+            return false;
+        }
+        let min = self
+            .cursors
+            .iter()
+            .flatten()
+            .copied()
+            .min()
+            .unwrap_or(self.end());
+        let mut trimmed = false;
+        while self.base < min && self.items.pop_front().is_some() {
+            self.base += 1;
+            trimmed = true;
+        }
+        trimmed
+    }
+
+    fn is_full(&self) -> bool {
+        self.capacity.is_some_and(|c| self.items.len() >= c)
+    }
+}
+
+/// State shared by the handles of [`Subscriber`] and [`Memoized`].
+pub(crate) struct Hub<G: AsyncGenerator> {
+    state: Mutex<State<G>>,
+    waiters: Arc<Waiters>,
+}
+
+impl<G: AsyncGenerator> Hub<G> {
+    pub(crate) fn new(gen: G, capacity: Option<usize>, overflow: Overflow) -> Self {
+        Self {
+            state: Mutex::new(State {
+                gen: Some(Box::pin(gen)),
+                polling: false,
+                items: VecDeque::new(),
+                base: 0,
+                ret: None,
+                cursors: Vec::new(),
+                capacity,
+                overflow,
+            }),
+            waiters: Arc::default(),
+        }
+    }
+
+    /// Lock the state.
+    ///
+    /// A panic while it's locked (for example, in `Clone` of a value) doesn't leave it
+    /// in a broken state, so the lock isn't poisoned.
+    fn lock(&self) -> MutexGuard<'_, State<G>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn add(&self, cursor: u64) -> usize {
+        let mut state = self.lock();
+        match state.cursors.iter().position(Option::is_none) {
+            Some(id) => {
+                state.cursors[id] = Some(cursor);
+                id
+            }
+            None => {
+                state.cursors.push(Some(cursor));
+                state.cursors.len() - 1
+            }
+        }
+    }
+
+    pub(crate) fn cursor(&self, id: usize) -> u64 {
+        self.lock().cursors[id].unwrap()
+    }
+
+    pub(crate) fn remove(&self, id: usize) {
+        let trimmed = {
+            let mut state = self.lock();
+            state.cursors[id] = None;
+            state.trim()
+        };
+        if trimmed {
+            self.waiters.wake_all();
+        }
+    }
+
+    pub(crate) fn return_value(&self) -> Option<Arc<G::Return>> {
+        self.lock().ret.clone()
+    }
+
+    /// Poll the next value for the handle, driving the generator if needed.
+    ///
+    /// Values, that the handle has skipped, are counted in `lagged`.
+    ///
+    /// The generator is polled without holding the lock, so its body can use the handles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the generator has panicked.
+    pub(crate) fn poll_next(
+        &self,
+        id: usize,
+        lagged: &mut u64,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<G::Yield, Arc<G::Return>>>
+    where
+        G::Yield: Clone,
+    {
+        let mut state = self.lock();
+        loop {
+            let cursor = state.cursors[id].unwrap();
+            if cursor < state.base {
+                *lagged += state.base - cursor;
+                state.cursors[id] = Some(state.base);
+                continue;
+            }
+            if cursor < state.end() {
+                let val = state.items[(cursor - state.base) as usize].clone();
+                state.cursors[id] = Some(cursor + 1);
+                let trimmed = state.trim();
+                drop(state);
+                if trimmed {
+                    self.waiters.wake_all();
+                }
+                return Poll::Ready(GeneratorState::Yielded(val));
+            }
+            if let Some(ret) = &state.ret {
+                return Poll::Ready(GeneratorState::Complete(ret.clone()));
+            }
+            // Register before polling, so that a wake up isn't missed.
+            self.waiters.register(cx.waker());
+            if state.is_full() && state.overflow == Overflow::Backpressure {
+                return Poll::Pending;
+            }
+            // Another handle is polling the generator, it wakes every handle once done.
+            if state.polling {
+                return Poll::Pending;
+            }
+            let mut gen = state.gen.take().expect("generator panicked during poll");
+            state.polling = true;
+            drop(state);
+
+            let reset = ResetOnPanic(self);
+            let waker = Waker::from(self.waiters.clone());
+            let poll = gen.as_mut().poll_resume(&mut Context::from_waker(&waker));
+            mem::forget(reset);
+
+            state = self.lock();
+            state.polling = false;
+            match poll {
+                Poll::Ready(GeneratorState::Yielded(val)) => {
+                    state.gen = Some(gen);
+                    state.items.push_back(val);
+                    if state.is_full() && state.items.len() > state.capacity.unwrap() {
+                        state.items.pop_front();
+                        state.base += 1;
+                    }
+                }
+                Poll::Ready(GeneratorState::Complete(val)) => {
+                    state.ret = Some(Arc::new(val));
+                    // The generator is dropped without holding the lock.
+                    drop(state);
+                    drop(gen);
+                    state = self.lock();
+                }
+                Poll::Pending => {
+                    state.gen = Some(gen);
+                    return Poll::Pending;
+                }
+            }
+            self.waiters.wake_all();
+        }
+    }
+}
+
+/// Marks the generator as no longer polled if it panics, and wakes the other handles,
+/// so that they observe the panic instead of waiting forever.
+struct ResetOnPanic<'a, G: AsyncGenerator>(&'a Hub<G>);
+
+impl<G: AsyncGenerator> Drop for ResetOnPanic<'_, G> {
+    fn drop(&mut self) {
+        self.0.lock().polling = false;
+        self.0.waiters.wake_all();
+    }
+}
+
+/// A subscriber of a broadcast generator.
+///
+/// Each subscriber receives every value yielded by the generator, from the point it
+/// was subscribed at, and the return value as an [`Arc`]. Cloning a subscriber creates
+/// a new subscriber, at the same position.
+///
+/// No task is spawned, the generator is driven by whichever subscriber polls it. At most
+/// `capacity` values are buffered, for subscribers that haven't received them yet.
+/// Then the [`Overflow`] policy applies.
+///
+/// If the generator panics, the panic propagates to the subscriber that polled it, and the
+/// other subscribers panic once they have received the buffered values.
+///
+/// This `struct` is created by [`AsyncGen::broadcast`] or [`AsyncGen::broadcast_with`].
+///
+/// ## Example
+///
+/// ```
+/// use async_gen::gen;
+/// use futures_util::{future::join, StreamExt};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let a = gen! {
+///     for i in 0..3 {
+///         yield i;
+///     }
+/// }
+/// .broadcast(2);
+/// let b = a.clone();
+///
+/// let (a, b) = join(a.collect::<Vec<_>>(), b.collect::<Vec<_>>()).await;
+/// assert_eq!(a, [0, 1, 2]);
+/// assert_eq!(b, [0, 1, 2]);
+/// # }
+/// ```
+pub struct Subscriber<G: AsyncGenerator> {
+    hub: Arc<Hub<G>>,
+    id: usize,
+    lagged: u64,
+}
+
+impl<G: AsyncGenerator> Subscriber<G> {
+    /// Broadcast the generator, see [`AsyncGen::broadcast_with`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(gen: G, capacity: usize, overflow: Overflow) -> Self {
+        assert!(capacity > 0, "capacity must be greater than zero");
+        let hub = Arc::new(Hub::new(gen, Some(capacity), overflow));
+        let id = hub.add(0);
+        Self { hub, id, lagged: 0 }
+    }
+
+    /// Returns the number of values this subscriber has skipped, because of [`Overflow::DropOldest`].
+    #[inline]
+    pub fn lagged(&self) -> u64 {
+        self.lagged
+    }
+
+    /// Returns the return value of the generator, if it has completed.
+    #[inline]
+    pub fn return_value(&self) -> Option<Arc<G::Return>> {
+        self.hub.return_value()
+    }
+}
+
+impl<G: AsyncGenerator> Clone for Subscriber<G> {
+    fn clone(&self) -> Self {
+        let id = self.hub.add(self.hub.cursor(self.id));
+        Self {
+            hub: self.hub.clone(),
+            id,
+            lagged: 0,
+        }
+    }
+}
+
+impl<G: AsyncGenerator> Drop for Subscriber<G> {
+    fn drop(&mut self) {
+        self.hub.remove(self.id);
+    }
+}
+
+impl<G: AsyncGenerator> Unpin for Subscriber<G> {}
+
+impl<G> AsyncGenerator for Subscriber<G>
+where
+    G: AsyncGenerator,
+    G::Yield: Clone,
+{
+    type Yield = G::Yield;
+    type Return = Arc<G::Return>;
+
+    fn poll_resume(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        let me = self.get_mut();
+        me.hub.poll_next(me.id, &mut me.lagged, cx)
+    }
+}
+
+impl_stream!(impl<G> Subscriber<G> where G: AsyncGenerator, G::Yield: Clone);
+
+impl<G: AsyncGenerator> fmt::Debug for Subscriber<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscriber")
+            .field("lagged", &self.lagged)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A handle to a memoized generator.
+///
+/// The generator runs once: whichever handle polls it first drives the body, and every
+/// yielded value is cached. Each handle has its own cursor, it replays the cached values
+/// before joining the live tail, then receives the cached return value as an [`Arc`].
+/// Cloning a handle creates a new handle, that starts from the first value.
+///
+/// Values are never evicted from the cache, until every handle is dropped.
+///
+/// If the generator panics, the panic propagates to the handle that polled it, and the
+/// other handles panic once they have replayed the cached values.
+///
+/// This `struct` is created by [`AsyncGen::memoize`].
+///
+/// ## Example
+///
+/// ```
+/// use async_gen::gen;
+/// use futures_util::StreamExt;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let scan = gen! {
+///     for file in ["a.txt", "b.txt"] {
+///         yield file;
+///     }
+/// }
+/// .memoize();
+///
+/// assert_eq!(scan.clone().collect::<Vec<_>>().await, ["a.txt", "b.txt"]);
+/// // Replayed from the cache, the body isn't run again.
+/// assert_eq!(scan.collect::<Vec<_>>().await, ["a.txt", "b.txt"]);
+/// # }
+/// ```
+pub struct Memoized<G: AsyncGenerator> {
+    hub: Arc<Hub<G>>,
+    id: usize,
+}
+
+impl<G: AsyncGenerator> Memoized<G> {
+    /// Memoize the generator, see [`AsyncGen::memoize`].
+    pub fn new(gen: G) -> Self {
+        let hub = Arc::new(Hub::new(gen, None, Overflow::Backpressure));
+        let id = hub.add(0);
+        Self { hub, id }
+    }
+
+    /// Returns the return value of the generator, if it has completed.
+    #[inline]
+    pub fn return_value(&self) -> Option<Arc<G::Return>> {
+        self.hub.return_value()
+    }
+}
+
+impl<G: AsyncGenerator> Clone for Memoized<G> {
+    fn clone(&self) -> Self {
+        Self {
+            hub: self.hub.clone(),
+            id: self.hub.add(0),
+        }
+    }
+}
+
+impl<G: AsyncGenerator> Drop for Memoized<G> {
+    fn drop(&mut self) {
+        self.hub.remove(self.id);
+    }
+}
+
+impl<G: AsyncGenerator> Unpin for Memoized<G> {}
+
+impl<G> AsyncGenerator for Memoized<G>
+where
+    G: AsyncGenerator,
+    G::Yield: Clone,
+{
+    type Yield = G::Yield;
+    type Return = Arc<G::Return>;
+
+    fn poll_resume(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        // Nothing is evicted, so nothing is skipped.
+        let mut lagged = 0;
+        self.hub.poll_next(self.id, &mut lagged, cx)
+    }
+}
+
+impl_stream!(impl<G> Memoized<G> where G: AsyncGenerator, G::Yield: Clone);
+
+impl<G: AsyncGenerator> fmt::Debug for Memoized<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Memoized").finish_non_exhaustive()
+    }
+}
+
+impl<Fut, Y, E, R> AsyncGen<Fut, Y, E>
+where
+    Fut: Future<Output = Return<R>>,
+{
+    /// Broadcast the generator to many subscribers.
+    ///
+    /// Same as [`AsyncGen::broadcast_with`] with [`Overflow::Backpressure`], so the slowest
+    /// subscriber controls how fast the generator runs.
+    ///
+    /// See [`Subscriber`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    #[inline]
+    pub fn broadcast(self, capacity: usize) -> Subscriber<Self> {
+        self.broadcast_with(capacity, Overflow::Backpressure)
+    }
+
+    /// Broadcast the generator to many subscribers, buffering up to `capacity` values.
+    ///
+    /// See [`Subscriber`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    #[inline]
+    pub fn broadcast_with(self, capacity: usize, overflow: Overflow) -> Subscriber<Self> {
+        Subscriber::new(self, capacity, overflow)
+    }
+
+    /// Memoize the generator, so that it can be consumed many times.
+    ///
+    /// See [`Memoized`] for more details.
+    #[inline]
+    pub fn memoize(self) -> Memoized<Self> {
+        Memoized::new(self)
+    }
+}
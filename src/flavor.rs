@@ -0,0 +1,235 @@
+//! How the state of a generator is shared with its body.
+//!
+//! [`ThreadSafe`] generators share it through an `Arc`, with atomics and a locked slot,
+//! so they can be sent across threads. [`Local`] generators share it through an `Rc`,
+//! with plain cells, so they are cheaper, but `!Send`.
+use crate::{
+    slot::{LocalSlot, Slot, SlotAccess},
+    State,
+};
+use std::{
+    cell::{Cell, OnceCell},
+    ops::Deref,
+    panic::Location,
+    ptr,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+};
+
+/// How the state of a generator is shared with its body, either [`ThreadSafe`] or [`Local`].
+///
+/// This trait is sealed, it can't be implemented outside of this crate.
+pub trait Flavor: sealed::Sealed {}
+
+/// The state of a generator is shared through an `Arc`, so it is `Send` (if its body is).
+///
+/// This is the flavor of [`AsyncGen`](crate::AsyncGen), created by [`gen()`](crate::gen()).
+#[derive(Debug)]
+pub enum ThreadSafe {}
+
+/// The state of a generator is shared through an `Rc`, so it is `!Send`.
+///
+/// This is the flavor of [`LocalAsyncGen`](crate::LocalAsyncGen), created by [`local_gen()`](crate::local_gen()).
+#[derive(Debug)]
+pub enum Local {}
+
+impl Flavor for ThreadSafe {}
+impl Flavor for Local {}
+
+pub(crate) mod sealed {
+    use super::*;
+
+    pub trait Sealed {
+        type Ptr<T>: Deref<Target = T> + Clone;
+        type Slot<T>: SlotAccess<T>;
+        type Usize: ValueCell<usize>;
+        type Bool: ValueCell<bool>;
+        type State: ValueCell<State>;
+        type Location: ValueCell<Option<&'static Location<'static>>>;
+        type Once<T>: OnceValue<T>;
+
+        fn new_ptr<T>(value: T) -> Self::Ptr<T>;
+    }
+
+    impl Sealed for ThreadSafe {
+        type Ptr<T> = Arc<T>;
+        type Slot<T> = Slot<T>;
+        type Usize = AtomicUsize;
+        type Bool = AtomicBool;
+        type State = AtomicState;
+        type Location = AtomicLocation;
+        type Once<T> = OnceLock<T>;
+
+        #[inline]
+        fn new_ptr<T>(value: T) -> Arc<T> {
+            Arc::new(value)
+        }
+    }
+
+    impl Sealed for Local {
+        type Ptr<T> = Rc<T>;
+        type Slot<T> = LocalSlot<T>;
+        type Usize = Cell<usize>;
+        type Bool = Cell<bool>;
+        type State = Cell<State>;
+        type Location = Cell<Option<&'static Location<'static>>>;
+        type Once<T> = OnceCell<T>;
+
+        #[inline]
+        fn new_ptr<T>(value: T) -> Rc<T> {
+            Rc::new(value)
+        }
+    }
+}
+
+/// A shared value, that is read and written as a whole.
+///
+/// Like `Cell<T>`, but either atomic or not, depending on the [`Flavor`].
+pub trait ValueCell<T> {
+    fn new(value: T) -> Self;
+    fn get(&self) -> T;
+    fn set(&self, value: T);
+}
+
+impl<T: Copy> ValueCell<T> for Cell<T> {
+    #[inline]
+    fn new(value: T) -> Self {
+        Cell::new(value)
+    }
+
+    #[inline]
+    fn get(&self) -> T {
+        Cell::get(self)
+    }
+
+    #[inline]
+    fn set(&self, value: T) {
+        Cell::set(self, value)
+    }
+}
+
+impl ValueCell<usize> for AtomicUsize {
+    #[inline]
+    fn new(value: usize) -> Self {
+        AtomicUsize::new(value)
+    }
+
+    #[inline]
+    fn get(&self) -> usize {
+        self.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn set(&self, value: usize) {
+        self.store(value, Ordering::Relaxed)
+    }
+}
+
+impl ValueCell<bool> for AtomicBool {
+    #[inline]
+    fn new(value: bool) -> Self {
+        AtomicBool::new(value)
+    }
+
+    #[inline]
+    fn get(&self) -> bool {
+        self.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn set(&self, value: bool) {
+        self.store(value, Ordering::Relaxed)
+    }
+}
+
+pub struct AtomicState(AtomicU8);
+
+impl ValueCell<State> for AtomicState {
+    #[inline]
+    fn new(value: State) -> Self {
+        AtomicState(AtomicU8::new(value as u8))
+    }
+
+    #[inline]
+    fn get(&self) -> State {
+        match self.0.load(Ordering::Relaxed) {
+            0 => State::NotStarted,
+            1 => State::SuspendedAtYield,
+            2 => State::Pending,
+            _ => State::Complete,
+        }
+    }
+
+    #[inline]
+    fn set(&self, value: State) {
+        self.0.store(value as u8, Ordering::Relaxed)
+    }
+}
+
+pub struct AtomicLocation(AtomicPtr<Location<'static>>);
+
+impl ValueCell<Option<&'static Location<'static>>> for AtomicLocation {
+    #[inline]
+    fn new(value: Option<&'static Location<'static>>) -> Self {
+        let ptr = value.map_or(ptr::null_mut(), |l| ptr::from_ref(l).cast_mut());
+        AtomicLocation(AtomicPtr::new(ptr))
+    }
+
+    #[inline]
+    fn get(&self) -> Option<&'static Location<'static>> {
+        // SEAFTY: The pointer is either null or set from a `&'static Location<'static>`
+        unsafe { self.0.load(Ordering::Relaxed).as_ref() }
+    }
+
+    #[inline]
+    fn set(&self, value: Option<&'static Location<'static>>) {
+        let ptr = value.map_or(ptr::null_mut(), |l| ptr::from_ref(l).cast_mut());
+        self.0.store(ptr, Ordering::Relaxed)
+    }
+}
+
+/// A value that is initialized at most once, on first use.
+///
+/// Like `OnceCell<T>`, but thread-safe or not, depending on the [`Flavor`].
+pub trait OnceValue<T> {
+    fn new() -> Self;
+    fn get(&self) -> Option<&T>;
+    fn get_or_init(&self, f: impl FnOnce() -> T) -> &T;
+}
+
+impl<T> OnceValue<T> for OnceCell<T> {
+    #[inline]
+    fn new() -> Self {
+        OnceCell::new()
+    }
+
+    #[inline]
+    fn get(&self) -> Option<&T> {
+        OnceCell::get(self)
+    }
+
+    #[inline]
+    fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        OnceCell::get_or_init(self, f)
+    }
+}
+
+impl<T> OnceValue<T> for OnceLock<T> {
+    #[inline]
+    fn new() -> Self {
+        OnceLock::new()
+    }
+
+    #[inline]
+    fn get(&self) -> Option<&T> {
+        OnceLock::get(self)
+    }
+
+    #[inline]
+    fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        OnceLock::get_or_init(self, f)
+    }
+}
@@ -0,0 +1,148 @@
+use crate::AsyncGenerator;
+use std::fmt;
+
+/// Conversion into an [`AsyncGenerator`].
+///
+/// Every generator implements it. `&GenFn` implements it too, so a generator factory
+/// can be restarted from the beginning, just like `&Vec<T>` can be iterated many times.
+pub trait IntoAsyncGenerator {
+    /// The type of value the generator yields.
+    type Yield;
+    /// The type of value the generator returns.
+    type Return;
+    /// Which kind of generator are we turning this into?
+    type IntoGen: AsyncGenerator<Yield = Self::Yield, Return = Self::Return>;
+
+    /// Creates a generator from a value.
+    fn into_async_gen(self) -> Self::IntoGen;
+}
+
+impl<G: AsyncGenerator> IntoAsyncGenerator for G {
+    type Yield = G::Yield;
+    type Return = G::Return;
+    type IntoGen = G;
+
+    #[inline]
+    fn into_async_gen(self) -> G {
+        self
+    }
+}
+
+/// A generator factory, that creates a fresh generator on each call.
+///
+/// Arguments are passed as a tuple. This `struct` is created by [`gen_fn!`](crate::gen_fn!)
+/// or [`GenFn::new`].
+///
+/// ## Examples
+///
+/// ```
+/// use async_gen::{gen_fn, GeneratorState};
+/// use std::pin::pin;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let countdown = gen_fn!(|from: u32| {
+///     for i in (1..=from).rev() {
+///         yield i;
+///     }
+/// });
+/// for _ in 0..2 {
+///     let mut g = pin!(countdown.call((2,)));
+///     assert_eq!(g.resume().await, GeneratorState::Yielded(2));
+///     assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+///     assert_eq!(g.resume().await, GeneratorState::Complete(()));
+/// }
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct GenFn<F> {
+    f: F,
+}
+
+impl<F> GenFn<F> {
+    /// Creates a generator factory from a function, that takes its arguments as a tuple.
+    #[inline]
+    pub fn new<Args, G>(f: F) -> Self
+    where
+        F: Fn(Args) -> G,
+        G: AsyncGenerator,
+    {
+        Self { f }
+    }
+
+    /// Creates a fresh generator, with the given arguments.
+    #[inline]
+    pub fn call<Args, G>(&self, args: Args) -> G
+    where
+        F: Fn(Args) -> G,
+        G: AsyncGenerator,
+    {
+        (self.f)(args)
+    }
+}
+
+impl<F> fmt::Debug for GenFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenFn").finish_non_exhaustive()
+    }
+}
+
+impl<F, G> IntoAsyncGenerator for &GenFn<F>
+where
+    F: Fn(()) -> G,
+    G: AsyncGenerator,
+{
+    type Yield = G::Yield;
+    type Return = G::Return;
+    type IntoGen = G;
+
+    #[inline]
+    fn into_async_gen(self) -> G {
+        self.call(())
+    }
+}
+
+/// A macro for creating a generator factory ([`GenFn`]).
+///
+/// The body is the same as [`gen!`](crate::gen!). Each call creates a fresh generator,
+/// so values captured from the environment are moved into each one of them. They
+/// must be `Copy`, or cloned explicitly.
+///
+/// ## Examples
+///
+/// ```
+/// use async_gen::{gen_fn, IntoAsyncGenerator};
+/// use futures_util::StreamExt;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let words = gen_fn!(|text: &str, max: usize| {
+///     for word in text.split(' ').take(max) {
+///         yield word;
+///     }
+/// });
+/// let g = words.call(("a b c", 2));
+/// assert_eq!(g.collect::<Vec<_>>().await, ["a", "b"]);
+///
+/// let hello = gen_fn!(|| {
+///     yield "hello";
+/// });
+/// for _ in 0..2 {
+///     let g = (&hello).into_async_gen();
+///     assert_eq!(g.collect::<Vec<_>>().await, ["hello"]);
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! gen_fn {
+    (|| { $($body:tt)* }) => {
+        $crate::gen_fn!(| | { $($body)* })
+    };
+    (|$($arg:ident : $ty:ty),* $(,)?| { $($body:tt)* }) => {
+        // Arguments aren't annotated in the closure, so that the generator can borrow them.
+        $crate::GenFn::new(move |($($arg,)*)| {
+            $(let $arg: $ty = $arg;)*
+            $crate::gen! { $($body)* }
+        })
+    };
+}
@@ -1,14 +1,76 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs)]
 
+pub use blocking::BlockingIter;
+pub use catch_unwind::CatchUnwind;
+pub use fanout::{Memoized, Overflow, Subscriber};
+pub use flavor::{Flavor, Local, ThreadSafe};
 pub use futures_core;
+pub use gen_fn::{GenFn, IntoAsyncGenerator};
+pub use local::{local_gen, local_gen_throwable, LocalAsyncGen, LocalYield};
+pub use merge::{merge, zip, Merge, Zip};
+pub use metrics::{Instrumented, Metered, MeteredStats};
+pub use set::GeneratorSet;
+pub use spawn::{Producer, Spawned};
+#[cfg(feature = "tracing")]
+pub use trace::Traced;
+
+/// Implements `Stream` for a generator adapter, the same way as [`AsyncIter`]:
+/// the stream yields the values of the generator, and ends when it completes.
+///
+/// Defined before the modules, so that they can use it.
+macro_rules! impl_stream {
+    (impl<$($param:ident),*> $ty:ty $(where $($bound:tt)*)?) => {
+        impl<$($param),*> futures_core::Stream for $ty
+        $(where $($bound)*)?
+        {
+            type Item = <Self as $crate::AsyncGenerator>::Yield;
+
+            #[inline]
+            fn poll_next(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<Option<Self::Item>> {
+                $crate::AsyncGenerator::poll_resume(self, cx).map($crate::GeneratorState::yielded)
+            }
+        }
+    };
+}
+
+mod blocking;
+mod catch_unwind;
+mod fanout;
+mod flavor;
+mod gen_fn;
+mod local;
+mod merge;
+mod metrics;
+pub mod record;
+mod set;
+mod slot;
+mod spawn;
+pub mod testing;
+#[cfg(feature = "tracing")]
+mod trace;
+pub mod watchdog;
+
+use flavor::{OnceValue, ValueCell};
 use pin_project_lite::pin_project;
+use slot::SlotAccess;
 use std::{
-    cell::UnsafeCell,
+    collections::VecDeque,
+    convert::Infallible,
+    fmt,
     future::{poll_fn, Future},
+    iter, mem,
+    ops::ControlFlow,
+    panic::Location,
     pin::Pin,
-    sync::Arc,
-    task::{Context, Poll},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Waker},
 };
 
 /// The result of a generator resumption.
@@ -17,6 +79,7 @@ use std::{
 /// possible return values of a generator. Currently this corresponds to either
 /// a suspension point (`Yielded`) or a termination point (`Complete`).
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GeneratorState<Y, R> {
     /// The generator suspended with a value.
     ///
@@ -34,6 +97,156 @@ pub enum GeneratorState<Y, R> {
     Complete(R),
 }
 
+impl<Y, R> GeneratorState<Y, R> {
+    /// Returns `true` if the state is [`Yielded`](GeneratorState::Yielded).
+    #[inline]
+    pub fn is_yielded(&self) -> bool {
+        matches!(self, GeneratorState::Yielded(_))
+    }
+
+    /// Returns `true` if the state is [`Complete`](GeneratorState::Complete).
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        matches!(self, GeneratorState::Complete(_))
+    }
+
+    /// Converts from `&GeneratorState<Y, R>` to `GeneratorState<&Y, &R>`.
+    #[inline]
+    pub fn as_ref(&self) -> GeneratorState<&Y, &R> {
+        match self {
+            GeneratorState::Yielded(val) => GeneratorState::Yielded(val),
+            GeneratorState::Complete(val) => GeneratorState::Complete(val),
+        }
+    }
+
+    /// Converts from `&mut GeneratorState<Y, R>` to `GeneratorState<&mut Y, &mut R>`.
+    #[inline]
+    pub fn as_mut(&mut self) -> GeneratorState<&mut Y, &mut R> {
+        match self {
+            GeneratorState::Yielded(val) => GeneratorState::Yielded(val),
+            GeneratorState::Complete(val) => GeneratorState::Complete(val),
+        }
+    }
+
+    /// Maps the yielded value with `f`, leaving a return value untouched.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use async_gen::GeneratorState;
+    ///
+    /// let state: GeneratorState<i32, &str> = GeneratorState::Yielded(2);
+    /// assert_eq!(state.map_yielded(|v| v * 10), GeneratorState::Yielded(20));
+    /// ```
+    #[inline]
+    pub fn map_yielded<T>(self, f: impl FnOnce(Y) -> T) -> GeneratorState<T, R> {
+        match self {
+            GeneratorState::Yielded(val) => GeneratorState::Yielded(f(val)),
+            GeneratorState::Complete(val) => GeneratorState::Complete(val),
+        }
+    }
+
+    /// Maps the return value with `f`, leaving a yielded value untouched.
+    #[inline]
+    pub fn map_complete<T>(self, f: impl FnOnce(R) -> T) -> GeneratorState<Y, T> {
+        match self {
+            GeneratorState::Yielded(val) => GeneratorState::Yielded(val),
+            GeneratorState::Complete(val) => GeneratorState::Complete(f(val)),
+        }
+    }
+
+    /// Returns the yielded value, or `None` if the generator has completed.
+    #[inline]
+    pub fn yielded(self) -> Option<Y> {
+        match self {
+            GeneratorState::Yielded(val) => Some(val),
+            GeneratorState::Complete(_) => None,
+        }
+    }
+
+    /// Returns the return value, or `None` if the generator has yielded.
+    #[inline]
+    pub fn complete(self) -> Option<R> {
+        match self {
+            GeneratorState::Yielded(_) => None,
+            GeneratorState::Complete(val) => Some(val),
+        }
+    }
+}
+
+impl<Y, R> GeneratorState<Option<Y>, R> {
+    /// Transposes a `GeneratorState` of an `Option` into an `Option` of a `GeneratorState`.
+    ///
+    /// `Yielded(None)` is mapped to `None`. `Yielded(Some(_))` and `Complete(_)`
+    /// are mapped to `Some(Yielded(_))` and `Some(Complete(_))`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use async_gen::GeneratorState;
+    ///
+    /// let state: GeneratorState<Option<i32>, ()> = GeneratorState::Yielded(Some(1));
+    /// assert_eq!(state.transpose(), Some(GeneratorState::Yielded(1)));
+    ///
+    /// let state: GeneratorState<Option<i32>, ()> = GeneratorState::Yielded(None);
+    /// assert_eq!(state.transpose(), None);
+    /// ```
+    #[inline]
+    pub fn transpose(self) -> Option<GeneratorState<Y, R>> {
+        match self {
+            GeneratorState::Yielded(Some(val)) => Some(GeneratorState::Yielded(val)),
+            GeneratorState::Yielded(None) => None,
+            GeneratorState::Complete(val) => Some(GeneratorState::Complete(val)),
+        }
+    }
+}
+
+/// `Yielded` is mapped to `Ok`, and `Complete` to `Err`.
+///
+/// So `?` returns early, once the generator has completed.
+impl<Y, R> From<GeneratorState<Y, R>> for Result<Y, R> {
+    #[inline]
+    fn from(state: GeneratorState<Y, R>) -> Self {
+        match state {
+            GeneratorState::Yielded(val) => Ok(val),
+            GeneratorState::Complete(val) => Err(val),
+        }
+    }
+}
+
+/// `Ok` is mapped to `Yielded`, and `Err` to `Complete`.
+impl<Y, R> From<Result<Y, R>> for GeneratorState<Y, R> {
+    #[inline]
+    fn from(result: Result<Y, R>) -> Self {
+        match result {
+            Ok(val) => GeneratorState::Yielded(val),
+            Err(val) => GeneratorState::Complete(val),
+        }
+    }
+}
+
+/// `Yielded` is mapped to `Continue`, and `Complete` to `Break`.
+impl<Y, R> From<GeneratorState<Y, R>> for ControlFlow<R, Y> {
+    #[inline]
+    fn from(state: GeneratorState<Y, R>) -> Self {
+        match state {
+            GeneratorState::Yielded(val) => ControlFlow::Continue(val),
+            GeneratorState::Complete(val) => ControlFlow::Break(val),
+        }
+    }
+}
+
+/// `Continue` is mapped to `Yielded`, and `Break` to `Complete`.
+impl<Y, R> From<ControlFlow<R, Y>> for GeneratorState<Y, R> {
+    #[inline]
+    fn from(flow: ControlFlow<R, Y>) -> Self {
+        match flow {
+            ControlFlow::Continue(val) => GeneratorState::Yielded(val),
+            ControlFlow::Break(val) => GeneratorState::Complete(val),
+        }
+    }
+}
+
 /// Generators, also commonly referred to as coroutines.
 pub trait AsyncGenerator {
     /// The type of value this generator yields.
@@ -84,87 +297,501 @@ pub trait AsyncGenerator {
     ) -> Poll<GeneratorState<Self::Yield, Self::Return>>;
 }
 
-struct Inner<Y> {
-    data: UnsafeCell<Option<Y>>,
+/// The execution state of a generator body.
+///
+/// This enum is returned from [`AsyncGen::state`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum State {
+    /// The generator body hasn't been polled yet.
+    NotStarted,
+    /// The generator body yielded a value, and is waiting to be resumed.
+    SuspendedAtYield,
+    /// The generator body is waiting on something else (for example, I/O).
+    Pending,
+    /// The generator body has completed with a return value.
+    Complete,
 }
 
-unsafe impl<Y: Send> Send for Inner<Y> {}
-unsafe impl<Y: Send + Sync> Sync for Inner<Y> {}
+/// How a generator body was resumed, from a `yield`.
+///
+/// This enum is returned from [`Yield::yield_`], and from `yield` in a `gen!` body
+/// (for example, `let resumed = yield value;`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Resumed {
+    /// The consumer took the value, and resumed the generator.
+    Next,
+    /// The generator is being closed (See [`AsyncGen::close`]).
+    ///
+    /// The body should run its cleanup and return. If it yields again, the value is
+    /// dropped, and `Closing` is returned immediately.
+    Closing,
+}
+
+impl Resumed {
+    /// Returns `true` if the generator is being closed.
+    #[inline]
+    pub fn is_closing(self) -> bool {
+        matches!(self, Resumed::Closing)
+    }
+}
+
+/// State handed off between the generator body and its consumer.
+struct Handoff<Y, E> {
+    /// Yielded values, that are not taken by the consumer yet.
+    data: VecDeque<Y>,
+    thrown: Option<E>,
+    /// Waker of the body, while it is suspended at a full buffer.
+    waker: Option<Waker>,
+}
+
+/// State shared by a generator and its body, atomic or not depending on the [`Flavor`].
+struct Inner<Y, E, F: Flavor> {
+    slot: F::Slot<Handoff<Y, E>>,
+    /// Maximum number of values in `data`, before the body is suspended.
+    capacity: F::Usize,
+    stop: F::Bool,
+    /// Allocated on first [`AsyncGen::stop_handle`] call, so a generator without handles
+    /// doesn't pay for it.
+    stop_handle: F::Once<StopHandle>,
+    closing: F::Bool,
+    state: F::State,
+    location: F::Location,
+}
+
+impl<Y, E, F: Flavor> Inner<Y, E, F> {
+    fn new() -> Self {
+        Inner {
+            slot: SlotAccess::new(Handoff {
+                data: VecDeque::new(),
+                thrown: None,
+                waker: None,
+            }),
+            capacity: ValueCell::new(1),
+            stop: ValueCell::new(false),
+            stop_handle: OnceValue::new(),
+            closing: ValueCell::new(false),
+            state: ValueCell::new(State::NotStarted),
+            location: ValueCell::new(None),
+        }
+    }
+
+    #[inline]
+    fn state(&self) -> State {
+        self.state.get()
+    }
+
+    #[inline]
+    fn set_state(&self, state: State) {
+        self.state.set(state);
+    }
+
+    #[inline]
+    fn location(&self) -> Option<&'static Location<'static>> {
+        self.location.get()
+    }
+
+    #[inline]
+    fn set_location(&self, location: &'static Location<'static>) {
+        self.location.set(Some(location));
+    }
+
+    /// Poll the generator body, and take the yielded value (if any).
+    ///
+    /// The body is polled while there is room in the buffer, then the oldest value is taken.
+    /// If the body completes while values are still buffered, its return value is stored
+    /// in `ret` until they are taken.
+    fn poll_body<Fut, R>(
+        &self,
+        fut: Pin<&mut Fut>,
+        ret: &mut Option<Return<R>>,
+        cx: &mut Context,
+    ) -> Poll<GeneratorState<Y, R>>
+    where
+        Fut: Future<Output = Return<R>>,
+    {
+        // Run ahead while there is room in the buffer, before handing out the oldest value.
+        if ret.is_none() && self.slot.consumer(|h| h.data.len()) < self.capacity.get() {
+            self.wake_body(cx);
+            if let Poll::Ready(val) = self.slot.poll_body(|| fut.poll(cx)) {
+                *ret = Some(val);
+            }
+        }
+        if let Some(val) = self.take() {
+            return Poll::Ready(GeneratorState::Yielded(val));
+        }
+        match ret.take() {
+            Some(Return(val)) => {
+                self.set_state(State::Complete);
+                Poll::Ready(GeneratorState::Complete(val))
+            }
+            None => {
+                self.set_state(State::Pending);
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Push yielded values, and wait until the consumer has taken enough of them.
+    ///
+    /// Only called from the generator body, through `&mut Yield` or `&mut LocalYield`.
+    async fn yield_iter(
+        &self,
+        vals: impl Iterator<Item = Y>,
+        location: &'static Location<'static>,
+    ) -> Resumed {
+        // Nobody will take the values, once the generator is closing.
+        if self.is_closing() {
+            drop(vals);
+            return Resumed::Closing;
+        }
+        self.set_location(location);
+        // Values are pushed one by one, so that `Iterator::next` isn't called while the slot is locked.
+        for val in vals {
+            self.slot.body(|h| h.data.push_back(val));
+        }
+        self.suspend().await;
+        if self.is_closing() {
+            Resumed::Closing
+        } else {
+            Resumed::Next
+        }
+    }
+
+    /// Wait until the consumer has taken enough values, to make room in the buffer.
+    ///
+    /// The waker is stored, because the `yield` may sit inside a combinator (for example,
+    /// `FuturesUnordered`) that only re-polls the children that were woken.
+    fn suspend(&self) -> impl Future<Output = ()> + '_ {
+        poll_fn(|cx| {
+            let capacity = self.capacity.get();
+            let registered = self.slot.body(|h| {
+                if h.data.len() < capacity {
+                    return None;
+                }
+                Some(h.waker.as_ref().is_some_and(|w| w.will_wake(cx.waker())))
+            });
+            match registered {
+                None => Poll::Ready(()),
+                Some(true) => Poll::Pending,
+                Some(false) => {
+                    let waker = cx.waker().clone();
+                    let prev = self.slot.body(|h| h.waker.replace(waker));
+                    drop(prev);
+                    Poll::Pending
+                }
+            }
+        })
+    }
+
+    /// Wake the body if it's suspended, and the consumer has made room in the buffer.
+    ///
+    /// Called right before the body is polled. If the body was suspended with the
+    /// consumer's own waker, it is going to be polled anyway, so it isn't woken.
+    fn wake_body(&self, cx: &Context) {
+        let capacity = self.capacity.get();
+        let waker = self.slot.consumer(|h| {
+            if h.data.len() < capacity {
+                h.waker.take()
+            } else {
+                None
+            }
+        });
+        if let Some(waker) = waker {
+            if !waker.will_wake(cx.waker()) {
+                waker.wake();
+            }
+        }
+    }
+
+    /// See [`Yield::yield_try`] for more details.
+    async fn yield_try(&self, val: Y, location: &'static Location<'static>) -> Result<(), E> {
+        if let Some(err) = self.take_thrown() {
+            return Err(err);
+        }
+        self.yield_iter(iter::once(val), location).await;
+        match self.take_thrown() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    #[inline]
+    fn take_thrown(&self) -> Option<E> {
+        self.slot.body(|h| h.thrown.take())
+    }
+
+    /// Set an error to be thrown into the generator body, at its next `yield_try`.
+    #[inline]
+    fn set_thrown(&self, err: E) {
+        let prev = self.slot.consumer(|h| h.thrown.replace(err));
+        drop(prev);
+    }
+
+    #[inline]
+    fn is_stop_requested(&self) -> bool {
+        self.stop.get()
+            || self
+                .stop_handle
+                .get()
+                .is_some_and(StopHandle::is_stop_requested)
+    }
+
+    #[inline]
+    fn request_stop(&self) {
+        self.stop.set(true);
+        if let Some(handle) = self.stop_handle.get() {
+            handle.request_stop();
+        }
+    }
+
+    fn stop_handle(&self) -> StopHandle {
+        let handle = self.stop_handle.get_or_init(|| StopHandle {
+            flag: Arc::new(AtomicBool::new(false)),
+        });
+        // A stop may have been requested before the handle was created.
+        if self.stop.get() {
+            handle.request_stop();
+        }
+        handle.clone()
+    }
+
+    #[inline]
+    fn is_closing(&self) -> bool {
+        self.closing.get()
+    }
+
+    /// Mark the generator as closing, and drop values that are not taken yet.
+    #[inline]
+    fn start_closing(&self) {
+        self.closing.set(true);
+        // Values are dropped after the slot is unlocked.
+        let data = self.slot.consumer(|h| mem::take(&mut h.data));
+        drop(data);
+    }
+
+    /// Take the oldest yielded value.
+    #[inline]
+    fn take(&self) -> Option<Y> {
+        let val = self.slot.consumer(|h| h.data.pop_front());
+        if val.is_some() {
+            self.set_state(State::SuspendedAtYield);
+        }
+        val
+    }
+}
+
+/// Cooperative scheduling budget of a generator.
+///
+/// See [`AsyncGen::with_budget`] for more details.
+enum Budget {
+    Unlimited,
+    /// Remaining consecutive ready polls, before yielding to the executor.
+    Limit {
+        limit: u32,
+        remaining: u32,
+    },
+    #[cfg(feature = "tokio")]
+    Tokio,
+}
+
+impl Budget {
+    #[inline]
+    fn poll<T>(&mut self, cx: &mut Context, f: impl FnOnce(&mut Context) -> Poll<T>) -> Poll<T> {
+        match self {
+            Budget::Unlimited => f(cx),
+            Budget::Limit { limit, remaining } => {
+                if *remaining == 0 {
+                    *remaining = *limit;
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                let poll = f(cx);
+                match poll {
+                    Poll::Ready(_) => *remaining -= 1,
+                    Poll::Pending => *remaining = *limit,
+                }
+                poll
+            }
+            #[cfg(feature = "tokio")]
+            Budget::Tokio => {
+                let coop = std::task::ready!(tokio::task::coop::poll_proceed(cx));
+                let poll = f(cx);
+                if poll.is_ready() {
+                    coop.made_progress();
+                }
+                poll
+            }
+        }
+    }
+}
 
 #[doc(hidden)]
-pub struct Yield<Y = ()> {
-    inner: Arc<Inner<Y>>,
+pub struct Yield<Y = (), E = Infallible, F: Flavor = ThreadSafe> {
+    inner: F::Ptr<Inner<Y, E, F>>,
 }
 
 #[doc(hidden)]
 pub struct Return<T = ()>(T);
 
-impl<Y> Yield<Y> {
+impl<Y, E, F: Flavor> Yield<Y, E, F> {
     /// Same as `yield` keyword.
     ///
     /// It pauses execution and the value is returned to the generator's caller.
-    pub async fn yield_(&mut self, val: Y) {
-        // SEAFTY: this function is marked with `&mut self`
-        //
-        // And `Yield<()>` can't escape from this closure:
-        //
-        // gen(|y: Yield<()>| async {
-        //     // `y` can't escape from this closure. and owned by `async` body
-        //     y.return_(())
-        // });
-        unsafe {
-            *self.inner.data.get() = Some(val);
-        }
+    ///
+    /// It resolves to [`Resumed::Closing`] if the generator is being closed
+    /// (See [`AsyncGen::close`]). The caller location is recorded, See [`AsyncGen::last_yield_location`].
+    #[inline]
+    #[track_caller]
+    pub fn yield_(&mut self, val: Y) -> impl Future<Output = Resumed> + '_ {
+        self.yield_at(val, Location::caller())
+    }
 
-        poll_fn(|_| {
-            if unsafe { (*self.inner.data.get()).is_some() } {
-                return Poll::Pending;
-            }
-            Poll::Ready(())
-        })
-        .await
+    /// Same as `yield_`, but records the given `location` as the yield point.
+    pub async fn yield_at(&mut self, val: Y, location: &'static Location<'static>) -> Resumed {
+        self.inner.yield_iter(iter::once(val), location).await
+    }
+
+    /// Same as `yield_`, but resolves to `Err` if an error was thrown into the generator.
+    ///
+    /// An error thrown (See [`AsyncGen::throw`]) while the generator is suspended
+    /// here is returned, so the body can handle it with `?` or recover from it.
+    /// An error thrown before reaching this point (for example, while suspended at
+    /// a plain `yield_`) is returned immediately, without yielding `val`.
+    ///
+    /// `gen!` macro supports it with `yield? value;` syntax.
+    #[track_caller]
+    pub fn yield_try(&mut self, val: Y) -> impl Future<Output = Result<(), E>> + '_ {
+        self.inner.yield_try(val, Location::caller())
+    }
+
+    /// Yield many values at once.
+    ///
+    /// All values are handed off to the consumer in a single suspension, which
+    /// reduces per-item overhead when combined with [`AsyncGen::poll_next_batch`].
+    /// The body resumes once the consumer has taken them.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::pin::pin;
+    /// use async_gen::gen;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut g = pin!(gen(|mut c| async move {
+    ///     c.yield_many([1, 2, 3]).await;
+    ///     c.yield_many(vec![4, 5]).await;
+    ///     c.return_(())
+    /// }));
+    /// let mut batch = Vec::new();
+    /// assert_eq!(g.next_batch(&mut batch, 16).await, Some(3));
+    /// assert_eq!(g.next_batch(&mut batch, 16).await, Some(2));
+    /// assert_eq!(g.next_batch(&mut batch, 16).await, None);
+    /// assert_eq!(batch, [1, 2, 3, 4, 5]);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn yield_many<'a, I>(&'a mut self, vals: I) -> impl Future<Output = Resumed> + 'a
+    where
+        I: IntoIterator<Item = Y>,
+        I::IntoIter: 'a,
+    {
+        self.inner.yield_iter(vals.into_iter(), Location::caller())
     }
 
     #[inline]
     pub fn return_<R>(self, _v: R) -> Return<R> {
         Return(_v)
     }
+
+    /// Returns `true` if the consumer has requested the generator to stop.
+    ///
+    /// The generator body should check this (typically after each `yield_`) and
+    /// return early, after releasing its resources.
+    ///
+    /// See [`AsyncGen::request_stop`] for more details.
+    #[inline]
+    pub fn stop_requested(&self) -> bool {
+        self.inner.is_stop_requested()
+    }
+
+    /// Returns `true` if the generator is being closed.
+    ///
+    /// Once closed, no more values will be taken. The generator body should run its
+    /// cleanup and return, without yielding again.
+    ///
+    /// See [`AsyncGen::close`] for more details.
+    #[inline]
+    pub fn is_closing(&self) -> bool {
+        self.inner.is_closing()
+    }
+}
+
+impl<Y, E, F: Flavor> fmt::Debug for Yield<Y, E, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Yield")
+            .field("state", &self.inner.state())
+            .field("last_yield_location", &self.inner.location())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A handle to request a graceful stop of a generator.
+///
+/// This `struct` is created by [`AsyncGen::stop_handle`]. It can be cloned and
+/// sent to other tasks, so that a stop can be requested without access to the generator.
+#[derive(Clone, Debug)]
+pub struct StopHandle {
+    flag: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    /// Request the generator to stop.
+    ///
+    /// This does not cancel the generator body, it is up to the body to check
+    /// [`Yield::stop_requested`] and return.
+    #[inline]
+    pub fn request_stop(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if a stop has been requested.
+    #[inline]
+    pub fn is_stop_requested(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
 }
 
 pin_project! {
     /// Represent an asyncronus generator. It implementations [`AsyncGenerator`] trait.
     ///
     /// This `struct` is created by [`gen()`]. See its documentation for more details.
-    pub struct AsyncGen<Fut, Y> {
-        inner: Arc<Inner<Y>>,
+    ///
+    /// With the [`Local`] flavor, it is a [`LocalAsyncGen`], created by [`local_gen()`].
+    pub struct AsyncGen<Fut, Y, E = Infallible, F = ThreadSafe>
+    where
+        Fut: Future,
+        F: Flavor,
+    {
+        inner: F::Ptr<Inner<Y, E, F>>,
+        budget: Budget,
+        ret: Option<Fut::Output>,
         #[pin]
         fut: Fut,
     }
 }
 
-impl<Fut, Y, R> AsyncGen<Fut, Y>
+impl<Fut, Y, E, R, F> AsyncGen<Fut, Y, E, F>
 where
     Fut: Future<Output = Return<R>>,
+    F: Flavor,
 {
     /// See [`AsyncGenerator::poll_resume`] for more details.
     #[doc(hidden)]
     pub fn poll_resume(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<GeneratorState<Y, R>> {
         let me = self.project();
-        match me.fut.poll(cx) {
-            Poll::Ready(Return(val)) => Poll::Ready(GeneratorState::Complete(val)),
-            Poll::Pending => {
-                // SEAFTY: We just return from `me.fut`,
-                // So this is safe and unique access to `me.inner.data`
-                unsafe {
-                    if (*me.inner.data.get()).is_some() {
-                        return Poll::Ready(GeneratorState::Yielded(
-                            (*me.inner.data.get()).take().unwrap_unchecked(),
-                        ));
-                    }
-                }
-                Poll::Pending
-            }
-        }
+        me.budget
+            .poll(cx, |cx| me.inner.poll_body(me.fut, me.ret, cx))
     }
 
     #[inline]
@@ -172,11 +799,288 @@ where
     pub async fn resume(self: &mut Pin<&mut Self>) -> GeneratorState<Y, R> {
         poll_fn(|cx| self.as_mut().poll_resume(cx)).await
     }
+
+    /// Close the generator, and wait for it to complete.
+    ///
+    /// The generator body is resumed from its current `yield_` point with
+    /// [`Yield::is_closing`] set, then it runs (including any async cleanup) to
+    /// completion. Values that were buffered (See [`AsyncGen::prefetch`]) are dropped.
+    ///
+    /// The body can observe it from the [`Resumed`] value of a `yield` (`let resumed = yield value;`
+    /// in a `gen!` body). If it yields again anyway, the value is dropped, and the body is
+    /// resumed immediately, so a body that never stops yielding is never closed.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::pin::pin;
+    /// use async_gen::{gen, GeneratorState};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut g = pin!(gen! {
+    ///     for i in 0.. {
+    ///         let resumed = yield i;
+    ///         if resumed.is_closing() {
+    ///             return i;
+    ///         }
+    ///     }
+    ///     unreachable!()
+    /// });
+    /// assert_eq!(g.resume().await, GeneratorState::Yielded(0));
+    /// assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    /// assert_eq!(g.close().await, 1);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Same as [`AsyncGenerator::poll_resume`], this function may panic if the generator
+    /// has already completed.
+    pub async fn close(self: &mut Pin<&mut Self>) -> R {
+        self.inner.start_closing();
+        loop {
+            if let GeneratorState::Complete(val) = self.resume().await {
+                return val;
+            }
+        }
+    }
+
+    /// Throw an error into the generator, at its current `yield_try` point.
+    ///
+    /// The suspended [`Yield::yield_try`] resolves to `Err(err)`, then the
+    /// generator is resumed. The body can propagate the error with `?` (which
+    /// completes the generator) or recover and continue yielding.
+    ///
+    /// Only generators created with [`gen_throwable()`] (or `gen!` with `yield?`)
+    /// can be thrown into.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::pin::pin;
+    /// use async_gen::{gen, GeneratorState};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut g = pin!(gen! {
+    ///     yield? 1;
+    ///     yield? 2;
+    ///     Ok::<_, &str>(())
+    /// });
+    /// assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    /// assert_eq!(g.throw("upstream failed").await, GeneratorState::Complete(Err("upstream failed")));
+    /// # }
+    /// ```
+    pub async fn throw(self: &mut Pin<&mut Self>, err: E) -> GeneratorState<Y, R> {
+        self.inner.set_thrown(err);
+        self.resume().await
+    }
 }
 
-impl<Fut, Y> AsyncGen<Fut, Y>
+impl<Fut: Future, Y, E, F: Flavor> AsyncGen<Fut, Y, E, F> {
+    /// Request the generator to stop gracefully.
+    ///
+    /// Unlike dropping the generator, the body keeps running until it observes
+    /// [`Yield::stop_requested`], so it can flush, release resources and return
+    /// a value. Keep resuming the generator to receive [`GeneratorState::Complete`].
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::pin::pin;
+    /// use async_gen::{gen, GeneratorState};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut g = pin!(gen(|mut c| async move {
+    ///     let mut count = 0;
+    ///     while !c.stop_requested() {
+    ///         c.yield_(count).await;
+    ///         count += 1;
+    ///     }
+    ///     c.return_(count)
+    /// }));
+    /// assert_eq!(g.resume().await, GeneratorState::Yielded(0));
+    /// assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    /// g.request_stop();
+    /// assert_eq!(g.resume().await, GeneratorState::Complete(2));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn request_stop(&self) {
+        self.inner.request_stop();
+    }
+
+    /// Let the generator body run ahead, and buffer up to `n` yielded values.
+    ///
+    /// By default, the body is suspended at each `yield` until the consumer takes
+    /// the value, so the producer sits idle while the consumer processes it. With
+    /// prefetch, the body keeps running until `n` values are buffered, and each resume
+    /// polls it again (while there is room) before taking the oldest buffered value.
+    ///
+    /// This doesn't spawn a task, the body still only runs while the generator is polled.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::pin::pin;
+    /// use async_gen::{gen, GeneratorState, State};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut g = pin!(gen! {
+    ///     for i in 0..3 {
+    ///         yield i;
+    ///     }
+    ///     return "done";
+    /// }
+    /// .prefetch(4));
+    ///
+    /// // The body runs to completion in the first poll.
+    /// assert_eq!(g.resume().await, GeneratorState::Yielded(0));
+    /// assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    /// assert_eq!(g.resume().await, GeneratorState::Yielded(2));
+    /// assert_eq!(g.resume().await, GeneratorState::Complete("done"));
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn prefetch(self, n: usize) -> Self {
+        assert!(n > 0, "prefetch must be greater than zero");
+        self.inner.capacity.set(n);
+        self
+    }
+
+    /// Limit the number of consecutive ready polls, before yielding to the executor.
+    ///
+    /// A generator that always yields ready values (for example, a `for` loop over
+    /// a `Vec`) never returns `Poll::Pending`, so a consumer draining it in a loop
+    /// never yields to the executor, and can starve other tasks. With a budget,
+    /// after `budget` consecutive ready polls, the generator wakes the current task
+    /// and returns `Poll::Pending` once.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use async_gen::gen;
+    /// use futures_util::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let g = gen! {
+    ///     for i in 0..1000 {
+    ///         yield i;
+    ///     }
+    /// }
+    /// .with_budget(32);
+    ///
+    /// assert_eq!(g.count().await, 1000);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `budget` is zero.
+    pub fn with_budget(mut self, budget: u32) -> Self {
+        assert!(budget > 0, "budget must be greater than zero");
+        self.budget = Budget::Limit {
+            limit: budget,
+            remaining: budget,
+        };
+        self
+    }
+
+    /// Use tokio's cooperative scheduling budget.
+    ///
+    /// Every ready poll consumes a unit of the current task's budget, and the generator
+    /// returns `Poll::Pending` once the budget is exhausted, just like tokio's own resources.
+    /// Outside of a tokio runtime, the budget is unlimited.
+    ///
+    /// See [`tokio::task::coop`] for more details.
+    #[cfg(feature = "tokio")]
+    pub fn with_coop_budget(mut self) -> Self {
+        self.budget = Budget::Tokio;
+        self
+    }
+
+    /// Returns a [`StopHandle`], that can be used to request a stop from elsewhere.
+    #[inline]
+    pub fn stop_handle(&self) -> StopHandle {
+        self.inner.stop_handle()
+    }
+
+    /// Returns the current execution state of the generator body.
+    ///
+    /// It reads a single value, without locking, so it's cheap to call.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::pin::pin;
+    /// use async_gen::{gen, State};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut g = pin!(gen! {
+    ///     yield 42;
+    /// });
+    /// assert_eq!(g.state(), State::NotStarted);
+    /// g.resume().await;
+    /// assert_eq!(g.state(), State::SuspendedAtYield);
+    /// g.resume().await;
+    /// assert_eq!(g.state(), State::Complete);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn state(&self) -> State {
+        self.inner.state()
+    }
+
+    /// Returns the source location of the latest `yield`, or `None` if the
+    /// generator hasn't yielded yet.
+    ///
+    /// When the generator is suspended, this is where the body is waiting. Useful
+    /// to find out which `yield` is stalled in a streaming pipeline.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::pin::pin;
+    /// use async_gen::gen;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let line = line!();
+    /// let mut g = pin!(gen! {
+    ///     yield 42;
+    /// });
+    /// g.resume().await;
+    /// let location = g.last_yield_location().unwrap();
+    /// assert_eq!((location.file(), location.line()), (file!(), line + 2));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn last_yield_location(&self) -> Option<&'static Location<'static>> {
+        self.inner.location()
+    }
+}
+
+impl<Fut: Future, Y, E, F: Flavor> fmt::Debug for AsyncGen<Fut, Y, E, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncGen")
+            .field("state", &self.state())
+            .field("last_yield_location", &self.last_yield_location())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Fut, Y, E, F> AsyncGen<Fut, Y, E, F>
 where
     Fut: Future<Output = Return<()>>,
+    F: Flavor,
 {
     #[inline]
     /// Creates an async iterator from this generator.
@@ -187,27 +1091,65 @@ where
     }
 
     #[doc(hidden)]
+    #[inline]
     pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Y>> {
-        let me = self.project();
-        match me.fut.poll(cx) {
-            Poll::Ready(Return(())) => Poll::Ready(None),
-            Poll::Pending => {
-                // SEAFTY: We just return from `me.fut`,
-                // So this is safe and unique access to `me.inner.data`
-                unsafe {
-                    if (*me.inner.data.get()).is_some() {
-                        return Poll::Ready((*me.inner.data.get()).take());
+        self.poll_resume(cx).map(|s| match s {
+            GeneratorState::Yielded(val) => Some(val),
+            GeneratorState::Complete(()) => None,
+        })
+    }
+
+    /// Attempt to pull out up to `max` values at once, and append them to `buf`.
+    ///
+    /// The generator is polled once, then values that are already buffered (See
+    /// [`Yield::yield_many`] and [`AsyncGen::prefetch`]) are taken without polling it again.
+    ///
+    /// Returns `Poll::Ready(Some(n))` with the number of values appended (at least one),
+    /// or `Poll::Ready(None)` if the generator has completed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub fn poll_next_batch(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut Vec<Y>,
+        max: usize,
+    ) -> Poll<Option<usize>> {
+        assert!(max > 0, "max must be greater than zero");
+        match self.as_mut().poll_resume(cx) {
+            Poll::Ready(GeneratorState::Yielded(val)) => {
+                buf.push(val);
+                let mut n = 1;
+                while n < max {
+                    match self.inner.take() {
+                        Some(val) => buf.push(val),
+                        None => break,
                     }
+                    n += 1;
                 }
-                Poll::Pending
+                Poll::Ready(Some(n))
             }
+            Poll::Ready(GeneratorState::Complete(())) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
         }
     }
+
+    /// See [`AsyncGen::poll_next_batch`] for more details.
+    #[inline]
+    pub async fn next_batch(
+        self: &mut Pin<&mut Self>,
+        buf: &mut Vec<Y>,
+        max: usize,
+    ) -> Option<usize> {
+        poll_fn(|cx| self.as_mut().poll_next_batch(cx, buf, max)).await
+    }
 }
 
-impl<Fut, Y> futures_core::Stream for AsyncGen<Fut, Y>
+impl<Fut, Y, E, F> futures_core::Stream for AsyncGen<Fut, Y, E, F>
 where
     Fut: Future<Output = Return<()>>,
+    F: Flavor,
 {
     type Item = Y;
 
@@ -217,9 +1159,10 @@ where
     }
 }
 
-impl<Fut, Y, R> AsyncGenerator for AsyncGen<Fut, Y>
+impl<Fut, Y, E, R, F> AsyncGenerator for AsyncGen<Fut, Y, E, F>
 where
     Fut: Future<Output = Return<R>>,
+    F: Flavor,
 {
     type Yield = Y;
     type Return = R;
@@ -283,15 +1226,15 @@ impl<G: AsyncGenerator<Return = ()>> AsyncIter<G> {
     /// async iterator state:
     ///
     /// - `Poll::Pending` means that this async iterator's next value is not ready
-    /// yet. Implementations will ensure that the current task will be notified
-    /// when the next value may be ready.
+    ///   yet. Implementations will ensure that the current task will be notified
+    ///   when the next value may be ready.
     ///
     /// - `Poll::Ready(Some(val))` means that the async iterator has successfully
-    /// produced a value, `val`, and may produce further values on subsequent
-    /// `poll_next` calls.
+    ///   produced a value, `val`, and may produce further values on subsequent
+    ///   `poll_next` calls.
     ///
     /// - `Poll::Ready(None)` means that the async iterator has terminated, and
-    /// `poll_next` should not be invoked again.
+    ///   `poll_next` should not be invoked again.
     ///
     /// # Panics
     ///
@@ -345,19 +1288,70 @@ pub fn gen<Fut, Y, R>(fut: impl FnOnce(Yield<Y>) -> Fut) -> AsyncGen<Fut, Y>
 where
     Fut: Future<Output = Return<R>>,
 {
-    let inner = Arc::new(Inner {
-        data: UnsafeCell::new(None),
-    });
+    gen_throwable(fut)
+}
+
+/// Creates a new generator, that errors of type `E` can be thrown into.
+///
+/// Same as [`gen()`], but the generator body receives errors thrown by
+/// [`AsyncGen::throw`] from [`Yield::yield_try`].
+///
+/// ## Examples
+///
+/// ```
+/// use std::pin::pin;
+/// use async_gen::{gen_throwable, GeneratorState};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut g = pin!(gen_throwable(|mut c| async move {
+///     let mut errors = 0;
+///     for i in 0..3 {
+///         if let Err(_err) = c.yield_try(i).await {
+///             errors += 1;
+///         }
+///     }
+///     c.return_(errors)
+/// }));
+/// assert_eq!(g.resume().await, GeneratorState::Yielded(0));
+/// assert_eq!(g.throw("recoverable").await, GeneratorState::Yielded(1));
+/// assert_eq!(g.resume().await, GeneratorState::Yielded(2));
+/// assert_eq!(g.resume().await, GeneratorState::Complete(1));
+/// # }
+/// ```
+pub fn gen_throwable<Fut, Y, R, E>(fut: impl FnOnce(Yield<Y, E>) -> Fut) -> AsyncGen<Fut, Y, E>
+where
+    Fut: Future<Output = Return<R>>,
+{
+    new_gen(fut)
+}
+
+/// Creates a new generator of any [`Flavor`].
+fn new_gen<Fut, Y, E, F: Flavor>(fut: impl FnOnce(Yield<Y, E, F>) -> Fut) -> AsyncGen<Fut, Y, E, F>
+where
+    Fut: Future,
+{
+    let inner = F::new_ptr(Inner::new());
     let fut = fut(Yield {
         inner: inner.clone(),
     });
-    AsyncGen { inner, fut }
+    AsyncGen {
+        inner,
+        budget: Budget::Unlimited,
+        ret: None,
+        fut,
+    }
 }
 
 /// A macro for creating generator.
 ///
 /// Also see [`gen()`] function for more details.
 ///
+/// `yield? value;` yields a value and propagates an error thrown into the generator
+/// (See [`AsyncGen::throw`]), in that case the generator is created with [`gen_throwable()`].
+/// The error is returned as is (without `From` conversion, unlike `?`), so the body
+/// must return a `Result` with the same error type.
+///
 /// ## Examples
 ///
 /// ```
@@ -382,6 +1376,39 @@ macro_rules! gen {
     }
 }
 
+/// A macro for creating single-threaded generator.
+///
+/// Same as [`gen!`], but creates a [`LocalAsyncGen`] with [`local_gen()`].
+/// So the generator body can hold `!Send` values across `yield`s.
+///
+/// ## Examples
+///
+/// ```
+/// use std::{cell::Cell, rc::Rc};
+/// use async_gen::local_gen;
+/// use futures_util::StreamExt;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let count = Rc::new(Cell::new(0));
+/// let counter = count.clone();
+/// let g = local_gen! {
+///     for i in 0..3 {
+///         counter.set(counter.get() + 1);
+///         yield i;
+///     }
+/// };
+/// assert_eq!(g.collect::<Vec<_>>().await, [0, 1, 2]);
+/// assert_eq!(count.get(), 3);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! local_gen {
+    ($($tt:tt)*) => {
+        $crate::__private::local_gen_inner!(($crate) $($tt)*)
+    }
+}
+
 #[doc(hidden)]
 pub mod __private {
     pub use async_gen_macros::*;
@@ -0,0 +1,65 @@
+use crate::{new_gen, AsyncGen, Local, Return, Yield};
+use std::{convert::Infallible, future::Future};
+
+/// Same as [`Yield`], but for [`LocalAsyncGen`].
+#[doc(hidden)]
+pub type LocalYield<Y = (), E = Infallible> = Yield<Y, E, Local>;
+
+/// A single-threaded generator. It implementations [`AsyncGenerator`](crate::AsyncGenerator) trait.
+///
+/// Same as [`AsyncGen`], but it is `!Send`, and its state is shared with the generator
+/// body through an [`Rc`](std::rc::Rc) instead of an `Arc`, without atomics (only a
+/// [`StopHandle`](crate::StopHandle), if requested, is shared through an `Arc`). So the
+/// body can hold `!Send` values (for example, `Rc<RefCell<T>>`) across `yield`s.
+/// Meant for single-threaded executors, such as [`tokio::task::LocalSet`].
+///
+/// This type is created by [`local_gen()`]. See its documentation for more details.
+///
+/// [`tokio::task::LocalSet`]: https://docs.rs/tokio/latest/tokio/task/struct.LocalSet.html
+pub type LocalAsyncGen<Fut, Y, E = Infallible> = AsyncGen<Fut, Y, E, Local>;
+
+/// Creates a new single-threaded generator.
+///
+/// Same as [`gen()`](crate::gen()), but returns a [`LocalAsyncGen`]. Also see [`local_gen!`](crate::local_gen!) macro.
+///
+/// ## Examples
+///
+/// ```
+/// use std::{cell::RefCell, pin::pin, rc::Rc};
+/// use async_gen::{local_gen, GeneratorState};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let log = Rc::new(RefCell::new(Vec::new()));
+/// let mut g = pin!(local_gen(|mut c| {
+///     let log = log.clone();
+///     async move {
+///         for i in 0..2 {
+///             log.borrow_mut().push(i);
+///             c.yield_(i).await;
+///         }
+///         c.return_(())
+///     }
+/// }));
+/// assert_eq!(g.resume().await, GeneratorState::Yielded(0));
+/// assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+/// assert_eq!(g.resume().await, GeneratorState::Complete(()));
+/// assert_eq!(*log.borrow(), [0, 1]);
+/// # }
+/// ```
+pub fn local_gen<Fut, Y, R>(fut: impl FnOnce(LocalYield<Y>) -> Fut) -> LocalAsyncGen<Fut, Y>
+where
+    Fut: Future<Output = Return<R>>,
+{
+    local_gen_throwable(fut)
+}
+
+/// Same as [`gen_throwable()`](crate::gen_throwable()), but returns a [`LocalAsyncGen`].
+pub fn local_gen_throwable<Fut, Y, R, E>(
+    fut: impl FnOnce(LocalYield<Y, E>) -> Fut,
+) -> LocalAsyncGen<Fut, Y, E>
+where
+    Fut: Future<Output = Return<R>>,
+{
+    new_gen(fut)
+}
@@ -0,0 +1,239 @@
+use crate::{AsyncGenerator, GeneratorState};
+use pin_project_lite::pin_project;
+use std::{
+    fmt, mem,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Merge many generators into one, that yields their values as they are ready.
+///
+/// Each value is tagged with the index of its source generator. Once every generator
+/// has completed, the merged generator returns their return values, in the same order.
+///
+/// ## Examples
+///
+/// ```
+/// use async_gen::{gen, merge, BlockingIter};
+///
+/// let shards = (0..2).map(|shard| gen! {
+///     yield shard * 10;
+///     return shard + 100;
+/// });
+/// let mut it = BlockingIter::new(merge(shards));
+/// assert_eq!(it.by_ref().collect::<Vec<_>>(), [(0, 0), (1, 10)]);
+/// assert_eq!(it.into_return(), Some(vec![100, 101]));
+/// ```
+pub fn merge<I>(gens: I) -> Merge<I::Item>
+where
+    I: IntoIterator,
+    I::Item: AsyncGenerator,
+{
+    let gens: Vec<_> = gens.into_iter().map(|gen| Some(Box::pin(gen))).collect();
+    Merge {
+        rets: gens.iter().map(|_| None).collect(),
+        remaining: gens.len(),
+        gens,
+        next: 0,
+        done: false,
+    }
+}
+
+/// A generator, that merges many generators.
+///
+/// Generators are polled in turn, starting after the one that yielded last, so that
+/// a generator that is always ready doesn't starve the others.
+///
+/// This `struct` is created by [`merge()`].
+pub struct Merge<G: AsyncGenerator> {
+    gens: Vec<Option<Pin<Box<G>>>>,
+    rets: Vec<Option<G::Return>>,
+    remaining: usize,
+    next: usize,
+    done: bool,
+}
+
+impl<G: AsyncGenerator> Unpin for Merge<G> {}
+
+impl<G: AsyncGenerator> AsyncGenerator for Merge<G> {
+    type Yield = (usize, G::Yield);
+    type Return = Vec<G::Return>;
+
+    fn poll_resume(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        assert!(!self.done, "`Merge` resumed after completion");
+        let len = self.gens.len();
+        for i in (self.next..len).chain(0..self.next) {
+            let Some(gen) = &mut self.gens[i] else {
+                continue;
+            };
+            match gen.as_mut().poll_resume(cx) {
+                Poll::Ready(GeneratorState::Yielded(val)) => {
+                    self.next = (i + 1) % len;
+                    return Poll::Ready(GeneratorState::Yielded((i, val)));
+                }
+                Poll::Ready(GeneratorState::Complete(val)) => {
+                    self.gens[i] = None;
+                    self.rets[i] = Some(val);
+                    self.remaining -= 1;
+                }
+                Poll::Pending => {}
+            }
+        }
+        if self.remaining > 0 {
+            return Poll::Pending;
+        }
+        self.done = true;
+        let rets = mem::take(&mut self.rets);
+        Poll::Ready(GeneratorState::Complete(
+            rets.into_iter().map(Option::unwrap).collect(),
+        ))
+    }
+}
+
+impl_stream!(impl<G> Merge<G> where G: AsyncGenerator<Return = ()>);
+
+impl<G: AsyncGenerator> fmt::Debug for Merge<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Merge")
+            .field("len", &self.gens.len())
+            .field("remaining", &self.remaining)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Zip two generators into one, that yields their values in pairs.
+///
+/// Once either generator completes, no more pairs are yielded, and the remaining values
+/// of the other generator are dropped. It is still run to completion, so that both return
+/// values are available.
+///
+/// The remaining values are drained one per poll, waking the current task in between, so a
+/// long generator doesn't block the executor. A generator that never completes, zipped with
+/// a shorter one, keeps the zipped generator from completing.
+///
+/// ## Examples
+///
+/// ```
+/// use async_gen::{gen, zip, BlockingIter};
+///
+/// let mut it = BlockingIter::new(zip(
+///     gen! {
+///         yield 'a';
+///         return "short";
+///     },
+///     gen! {
+///         yield 1;
+///         yield 2;
+///         return "long";
+///     },
+/// ));
+/// assert_eq!(it.by_ref().collect::<Vec<_>>(), [('a', 1)]);
+/// assert_eq!(it.into_return(), Some(("short", "long")));
+/// ```
+pub fn zip<A, B>(a: A, b: B) -> Zip<A, B>
+where
+    A: AsyncGenerator,
+    B: AsyncGenerator,
+{
+    Zip {
+        a,
+        b,
+        a_val: None,
+        b_val: None,
+        a_ret: None,
+        b_ret: None,
+        done: false,
+    }
+}
+
+pin_project! {
+    /// A generator, that zips two generators.
+    ///
+    /// This `struct` is created by [`zip()`].
+    pub struct Zip<A: AsyncGenerator, B: AsyncGenerator> {
+        #[pin]
+        a: A,
+        #[pin]
+        b: B,
+        a_val: Option<A::Yield>,
+        b_val: Option<B::Yield>,
+        a_ret: Option<A::Return>,
+        b_ret: Option<B::Return>,
+        done: bool,
+    }
+}
+
+/// Poll the generator, unless it has already produced a value or completed.
+fn poll_side<G: AsyncGenerator>(
+    gen: Pin<&mut G>,
+    val: &mut Option<G::Yield>,
+    ret: &mut Option<G::Return>,
+    cx: &mut Context<'_>,
+) {
+    if val.is_some() || ret.is_some() {
+        return;
+    }
+    match gen.poll_resume(cx) {
+        Poll::Ready(GeneratorState::Yielded(v)) => *val = Some(v),
+        Poll::Ready(GeneratorState::Complete(r)) => *ret = Some(r),
+        Poll::Pending => {}
+    }
+}
+
+impl<A: AsyncGenerator, B: AsyncGenerator> AsyncGenerator for Zip<A, B> {
+    type Yield = (A::Yield, B::Yield);
+    type Return = (A::Return, B::Return);
+
+    fn poll_resume(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        let mut me = self.project();
+        assert!(!*me.done, "`Zip` resumed after completion");
+        if me.a_ret.is_none() && me.b_ret.is_none() {
+            poll_side(me.a.as_mut(), me.a_val, me.a_ret, cx);
+            poll_side(me.b.as_mut(), me.b_val, me.b_ret, cx);
+            if me.a_val.is_some() && me.b_val.is_some() {
+                let pair = (me.a_val.take().unwrap(), me.b_val.take().unwrap());
+                return Poll::Ready(GeneratorState::Yielded(pair));
+            }
+            if me.a_ret.is_none() && me.b_ret.is_none() {
+                return Poll::Pending;
+            }
+        }
+        // One side has completed, a value without a pair is dropped.
+        *me.a_val = None;
+        *me.b_val = None;
+        if me.a_ret.is_none() {
+            poll_side(me.a.as_mut(), me.a_val, me.a_ret, cx);
+        } else if me.b_ret.is_none() {
+            poll_side(me.b.as_mut(), me.b_val, me.b_ret, cx);
+        }
+        // Drain at most one value per poll.
+        if me.a_val.take().is_some() || me.b_val.take().is_some() {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        if me.a_ret.is_none() || me.b_ret.is_none() {
+            return Poll::Pending;
+        }
+        *me.done = true;
+        Poll::Ready(GeneratorState::Complete((
+            me.a_ret.take().unwrap(),
+            me.b_ret.take().unwrap(),
+        )))
+    }
+}
+
+impl_stream!(impl<A, B> Zip<A, B> where A: AsyncGenerator<Return = ()>, B: AsyncGenerator<Return = ()>);
+
+impl<A: AsyncGenerator, B: AsyncGenerator> fmt::Debug for Zip<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Zip")
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
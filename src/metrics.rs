@@ -0,0 +1,126 @@
+use crate::{AsyncGenerator, GeneratorState};
+use pin_project_lite::pin_project;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// A snapshot of the metrics collected by [`Metered`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MeteredStats {
+    /// Number of times the generator was polled.
+    pub polls: u64,
+    /// Number of polls that returned `Poll::Pending`.
+    pub pending_polls: u64,
+    /// Number of values the generator yielded.
+    pub yields: u64,
+    /// Total time spent inside the generator body.
+    pub busy: Duration,
+    /// Total time the generator body was pending, waiting to be woken up (for example, by I/O).
+    ///
+    /// A large value indicates a slow producer.
+    pub pending: Duration,
+    /// Total time the generator was suspended at a `yield`, waiting for the consumer to resume it.
+    ///
+    /// A large value indicates a slow consumer.
+    pub suspended: Duration,
+}
+
+pin_project! {
+    /// A generator wrapper, that collects metrics about its execution.
+    ///
+    /// It counts polls, pending polls and yields, and measures the time spent
+    /// inside the generator body compared with the time spent waiting, either for
+    /// the body to be woken or for the consumer to take the next value.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::pin::pin;
+    /// use async_gen::{gen, Metered};
+    /// use futures_util::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut g = pin!(Metered::new(gen! {
+    ///     yield 1;
+    ///     yield 2;
+    /// }));
+    /// while let Some(_) = g.next().await {}
+    ///
+    /// let stats = g.stats();
+    /// assert_eq!(stats.yields, 2);
+    /// assert_eq!(stats.polls, 3);
+    /// # }
+    /// ```
+    pub struct Metered<G> {
+        #[pin]
+        gen: G,
+        stats: MeteredStats,
+        // When the generator returned `Pending` or `Yielded` (`true` if yielded).
+        suspended_at: Option<(Instant, bool)>,
+    }
+}
+
+/// Same as [`Metered`], under the name it was requested with.
+///
+/// The wrapper is named `Metered`, so it isn't confused with `tracing`'s `Instrumented`,
+/// when the `tracing` feature is enabled.
+pub type Instrumented<G> = Metered<G>;
+
+impl<G> Metered<G> {
+    /// Wrap the generator, to collect its metrics.
+    #[inline]
+    pub fn new(gen: G) -> Self {
+        Self {
+            gen,
+            stats: MeteredStats::default(),
+            suspended_at: None,
+        }
+    }
+
+    /// Returns a snapshot of the metrics collected so far.
+    #[inline]
+    pub fn stats(&self) -> MeteredStats {
+        self.stats
+    }
+}
+
+impl<G: AsyncGenerator> AsyncGenerator for Metered<G> {
+    type Yield = G::Yield;
+    type Return = G::Return;
+
+    fn poll_resume(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        let me = self.project();
+        let start = Instant::now();
+        match me.suspended_at.take() {
+            Some((at, true)) => me.stats.suspended += start - at,
+            Some((at, false)) => me.stats.pending += start - at,
+            None => {}
+        }
+
+        let state = me.gen.poll_resume(cx);
+
+        let end = Instant::now();
+        me.stats.busy += end - start;
+        me.stats.polls += 1;
+        match state {
+            Poll::Ready(GeneratorState::Yielded(_)) => {
+                me.stats.yields += 1;
+                *me.suspended_at = Some((end, true));
+            }
+            Poll::Pending => {
+                me.stats.pending_polls += 1;
+                *me.suspended_at = Some((end, false));
+            }
+            Poll::Ready(GeneratorState::Complete(_)) => {}
+        }
+        state
+    }
+}
+
+impl_stream!(impl<G> Metered<G> where G: AsyncGenerator<Return = ()>);
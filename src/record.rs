@@ -0,0 +1,228 @@
+//! Record the output of a generator, and play it back.
+//!
+//! [`Recorder`] snapshots the output of a generator once, then [`Replay`] plays it back
+//! in downstream tests, without running the real (for example, I/O bound) generator body.
+use crate::{AsyncGenerator, GeneratorState};
+use pin_project_lite::pin_project;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// An event of a generator, recorded by [`Recorder`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event<Y, R> {
+    /// The generator yielded a value.
+    Yielded(Y),
+    /// The generator returned `Poll::Pending`.
+    Pending,
+    /// The generator completed with a return value.
+    Complete(R),
+}
+
+/// A recorded [`Event`], with the time it was recorded at.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry<Y, R> {
+    /// Time since the first poll, if timing is recorded (See [`Recorder::with_timing`]).
+    pub at: Option<Duration>,
+    /// The recorded event.
+    pub event: Event<Y, R>,
+}
+
+/// The events recorded by [`Recorder`], that can be played back with [`Replay`].
+///
+/// With the `serde` feature, it can be serialized, for example to a snapshot file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recording<Y, R> {
+    /// The recorded entries, in order.
+    pub entries: Vec<Entry<Y, R>>,
+}
+
+impl<Y, R> Recording<Y, R> {
+    /// Returns `true` if the recorded generator has completed.
+    pub fn is_complete(&self) -> bool {
+        matches!(
+            self.entries.last(),
+            Some(Entry {
+                event: Event::Complete(_),
+                ..
+            })
+        )
+    }
+
+    /// Creates a generator, that plays back this recording.
+    #[inline]
+    pub fn replay(self) -> Replay<Y, R> {
+        Replay::new(self)
+    }
+}
+
+pin_project! {
+    /// A generator wrapper, that records every state produced by the generator.
+    ///
+    /// Yielded values and the return value are cloned into a [`Recording`], which can
+    /// be played back with [`Replay`]. Optionally, `Pending` gaps and timing are recorded too.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::pin::pin;
+    /// use async_gen::{gen, record::{Event, Recorder}};
+    /// use futures_util::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut g = pin!(Recorder::new(gen! {
+    ///     yield 1;
+    ///     yield 2;
+    /// }));
+    /// while let Some(_) = g.next().await {}
+    ///
+    /// let recording = g.recording().clone();
+    /// let events: Vec<_> = recording.entries.iter().map(|e| e.event.clone()).collect();
+    /// assert_eq!(events, [Event::Yielded(1), Event::Yielded(2), Event::Complete(())]);
+    ///
+    /// let replayed: Vec<_> = recording.replay().collect().await;
+    /// assert_eq!(replayed, [1, 2]);
+    /// # }
+    /// ```
+    pub struct Recorder<G: AsyncGenerator> {
+        #[pin]
+        gen: G,
+        recording: Recording<G::Yield, G::Return>,
+        pending: bool,
+        timing: bool,
+        started: Option<Instant>,
+    }
+}
+
+impl<G: AsyncGenerator> Recorder<G> {
+    /// Wrap the generator, to record its states.
+    #[inline]
+    pub fn new(gen: G) -> Self {
+        Self {
+            gen,
+            recording: Recording {
+                entries: Vec::new(),
+            },
+            pending: false,
+            timing: false,
+            started: None,
+        }
+    }
+
+    /// Also record when the generator returns `Poll::Pending`.
+    #[inline]
+    pub fn with_pending(mut self) -> Self {
+        self.pending = true;
+        self
+    }
+
+    /// Also record the time of each event, since the first poll.
+    #[inline]
+    pub fn with_timing(mut self) -> Self {
+        self.timing = true;
+        self
+    }
+
+    /// Returns the events recorded so far.
+    #[inline]
+    pub fn recording(&self) -> &Recording<G::Yield, G::Return> {
+        &self.recording
+    }
+
+    /// Consumes the recorder, and returns the recorded events.
+    #[inline]
+    pub fn into_recording(self) -> Recording<G::Yield, G::Return> {
+        self.recording
+    }
+}
+
+impl<G> AsyncGenerator for Recorder<G>
+where
+    G: AsyncGenerator,
+    G::Yield: Clone,
+    G::Return: Clone,
+{
+    type Yield = G::Yield;
+    type Return = G::Return;
+
+    fn poll_resume(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        let me = self.project();
+        let started = *me.started.get_or_insert_with(Instant::now);
+        let state = me.gen.poll_resume(cx);
+        let event = match &state {
+            Poll::Ready(GeneratorState::Yielded(val)) => Event::Yielded(val.clone()),
+            Poll::Ready(GeneratorState::Complete(val)) => Event::Complete(val.clone()),
+            Poll::Pending if *me.pending => Event::Pending,
+            Poll::Pending => return Poll::Pending,
+        };
+        me.recording.entries.push(Entry {
+            at: me.timing.then(|| started.elapsed()),
+            event,
+        });
+        state
+    }
+}
+
+impl_stream!(impl<G> Recorder<G> where G: AsyncGenerator<Return = ()>, G::Yield: Clone);
+
+/// A generator, that plays back a [`Recording`].
+///
+/// It has the same `Yield` and `Return` types as the recorded generator. A recorded
+/// `Pending` wakes the current task and returns `Poll::Pending` once. Timing is not
+/// replayed, each event is produced as soon as the generator is polled.
+///
+/// This `struct` is created by [`Recording::replay`] or [`Replay::new`].
+#[derive(Debug)]
+pub struct Replay<Y, R> {
+    entries: std::vec::IntoIter<Entry<Y, R>>,
+}
+
+impl<Y, R> Replay<Y, R> {
+    /// Creates a generator, that plays back the recording.
+    #[inline]
+    pub fn new(recording: Recording<Y, R>) -> Self {
+        Self {
+            entries: recording.entries.into_iter(),
+        }
+    }
+}
+
+impl<Y, R> Unpin for Replay<Y, R> {}
+
+impl<Y, R> AsyncGenerator for Replay<Y, R> {
+    type Yield = Y;
+    type Return = R;
+
+    /// # Panics
+    ///
+    /// Panics if there are no more recorded events, for example after completion,
+    /// or if the recording is incomplete (See [`Recording::is_complete`]).
+    fn poll_resume(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        let entry = self
+            .entries
+            .next()
+            .expect("no more recorded events to replay");
+        match entry.event {
+            Event::Yielded(val) => Poll::Ready(GeneratorState::Yielded(val)),
+            Event::Complete(val) => Poll::Ready(GeneratorState::Complete(val)),
+            Event::Pending => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl_stream!(impl<Y> Replay<Y, ()>);
@@ -0,0 +1,258 @@
+use crate::{AsyncGenerator, GeneratorState};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    hash::Hash,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+/// Entries that were woken, and the waker of the task polling the set.
+#[derive(Default)]
+struct ReadyQueue {
+    queue: Mutex<VecDeque<Arc<EntryWaker>>>,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl ReadyQueue {
+    fn push(&self, entry: Arc<EntryWaker>) {
+        if entry.queued.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.queue.lock().unwrap().push_back(entry);
+        let waker = self.waker.lock().unwrap().take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn pop(&self) -> Option<Arc<EntryWaker>> {
+        let entry = self.queue.lock().unwrap().pop_front()?;
+        entry.queued.store(false, Ordering::Release);
+        Some(entry)
+    }
+
+    fn register(&self, waker: &Waker) {
+        let mut slot = self.waker.lock().unwrap();
+        if !slot.as_ref().is_some_and(|w| w.will_wake(waker)) {
+            *slot = Some(waker.clone());
+        }
+    }
+}
+
+/// The waker of an entry, that queues it when woken.
+struct EntryWaker {
+    index: usize,
+    queued: AtomicBool,
+    // Weak, as the queue holds the entry wakers.
+    ready: Weak<ReadyQueue>,
+}
+
+impl Wake for EntryWaker {
+    fn wake(self: Arc<Self>) {
+        if let Some(ready) = self.ready.upgrade() {
+            ready.push(self);
+        }
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if let Some(ready) = self.ready.upgrade() {
+            ready.push(self.clone());
+        }
+    }
+}
+
+struct Entry<K, G> {
+    key: K,
+    gen: Pin<Box<G>>,
+    waker: Arc<EntryWaker>,
+}
+
+/// A set of generators, that are driven concurrently.
+///
+/// Like `FuturesUnordered`, each generator gets its own waker, so only the generators
+/// that were woken are polled. Events of every generator are yielded as a [`Stream`](futures_core::Stream),
+/// tagged with the key of their generator. A generator is removed from the set once it completes.
+///
+/// The stream ends when the set is empty, but generators can still be inserted afterwards.
+///
+/// ## Example
+///
+/// ```
+/// use async_gen::{gen, GeneratorSet, GeneratorState};
+/// use futures_util::StreamExt;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut set = GeneratorSet::new();
+/// for id in ["a", "b"] {
+///     set.insert(id, gen! {
+///         yield id.len();
+///         return id;
+///     });
+/// }
+///
+/// let mut returned = Vec::new();
+/// while let Some((key, state)) = set.next().await {
+///     if let GeneratorState::Complete(val) = state {
+///         assert_eq!(key, val);
+///         returned.push(val);
+///     }
+/// }
+/// returned.sort();
+/// assert_eq!(returned, ["a", "b"]);
+/// # }
+/// ```
+pub struct GeneratorSet<K, G> {
+    entries: Vec<Option<Entry<K, G>>>,
+    free: Vec<usize>,
+    keys: HashMap<K, usize>,
+    ready: Arc<ReadyQueue>,
+}
+
+impl<K, G> GeneratorSet<K, G>
+where
+    K: Hash + Eq + Clone,
+    G: AsyncGenerator,
+{
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            free: Vec::new(),
+            keys: HashMap::new(),
+            ready: Arc::default(),
+        }
+    }
+
+    /// Returns the number of generators in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if the set contains no generators.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns `true` if the set contains a generator with the key.
+    #[inline]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.keys.contains_key(key)
+    }
+
+    /// Insert a generator into the set.
+    ///
+    /// If the set already had a generator with the key, it's replaced and returned.
+    pub fn insert(&mut self, key: K, gen: G) -> Option<Pin<Box<G>>> {
+        let old = self.remove(&key);
+        let index = self.free.pop().unwrap_or(self.entries.len());
+        let waker = Arc::new(EntryWaker {
+            index,
+            queued: AtomicBool::new(false),
+            ready: Arc::downgrade(&self.ready),
+        });
+        let entry = Entry {
+            key: key.clone(),
+            gen: Box::pin(gen),
+            waker: waker.clone(),
+        };
+        if index == self.entries.len() {
+            self.entries.push(Some(entry));
+        } else {
+            self.entries[index] = Some(entry);
+        }
+        self.keys.insert(key, index);
+        // A new generator must be polled once, to start it.
+        self.ready.push(waker);
+        old
+    }
+
+    /// Remove the generator with the key from the set, and return it.
+    pub fn remove(&mut self, key: &K) -> Option<Pin<Box<G>>> {
+        let index = self.keys.remove(key)?;
+        let entry = self.entries[index].take()?;
+        self.free.push(index);
+        Some(entry.gen)
+    }
+}
+
+impl<K, G> Default for GeneratorSet<K, G>
+where
+    K: Hash + Eq + Clone,
+    G: AsyncGenerator,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, G> Unpin for GeneratorSet<K, G> {}
+
+impl<K, G> futures_core::Stream for GeneratorSet<K, G>
+where
+    K: Hash + Eq + Clone,
+    G: AsyncGenerator,
+{
+    type Item = (K, GeneratorState<G::Yield, G::Return>);
+
+    /// Poll the generators that were woken, until one of them yields or completes.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = self.get_mut();
+        me.ready.register(cx.waker());
+        // Yield back to the runtime eventually, if generators keep waking themselves.
+        let mut budget = me.entries.len().max(1);
+        while let Some(waker) = me.ready.pop() {
+            let Some(entry) = &mut me.entries[waker.index] else {
+                continue;
+            };
+            // A stale waker, of a removed generator.
+            if !Arc::ptr_eq(&entry.waker, &waker) {
+                continue;
+            }
+            let entry_waker = Waker::from(waker.clone());
+            match entry
+                .gen
+                .as_mut()
+                .poll_resume(&mut Context::from_waker(&entry_waker))
+            {
+                Poll::Ready(GeneratorState::Yielded(val)) => {
+                    let key = entry.key.clone();
+                    // It may yield again without being woken.
+                    me.ready.push(waker);
+                    return Poll::Ready(Some((key, GeneratorState::Yielded(val))));
+                }
+                Poll::Ready(GeneratorState::Complete(val)) => {
+                    let entry = me.entries[waker.index].take().unwrap();
+                    me.keys.remove(&entry.key);
+                    me.free.push(waker.index);
+                    return Poll::Ready(Some((entry.key, GeneratorState::Complete(val))));
+                }
+                Poll::Pending => {}
+            }
+            budget -= 1;
+            if budget == 0 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
+        if me.is_empty() {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+}
+
+impl<K, G> fmt::Debug for GeneratorSet<K, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GeneratorSet")
+            .field("len", &self.keys.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,196 @@
+//! The handoff between a generator body and its consumer.
+//!
+//! All unchecked access to the state shared by [`Yield`](crate::Yield) and
+//! [`AsyncGen`](crate::AsyncGen) goes through [`Slot`], so the protocol is audited here,
+//! instead of at every call site.
+//!
+//! # Invariants
+//!
+//! 1. The body side (`Slot::body`) is only accessed while the generator body is being
+//!    polled (`Slot::poll_body`), and the consumer side (`Slot::consumer`) only outside of it.
+//!    Both sides are reached through `Pin<&mut AsyncGen>`, so they never overlap.
+//! 2. Closures passed to `Slot::body` and `Slot::consumer` never run user code (for example,
+//!    `Drop` of a yielded value, or `Iterator::next`), so the slot is never accessed re-entrantly.
+//!
+//! A `Yield` can escape the generator body, for example by moving it out of the closure passed
+//! to `gen()`, and then it can be used from another thread at any time. That breaks (1), so
+//! every access also takes a lock. It is a single uncontended compare-exchange when (1) holds,
+//! and makes the escaped case serialized instead of a data race.
+//!
+//! With `debug_assertions`, (1) is checked, and breaking it panics instead.
+//!
+//! A `LocalYield` can't leave its thread, so [`LocalSlot`] needs no lock. It is a `RefCell`,
+//! so an escaped `LocalYield` still panics instead of aliasing the value.
+
+#[cfg(loom)]
+use loom::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use std::cell::{Cell, RefCell};
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether the protocol is checked at runtime.
+///
+/// Loom explores the unchecked behavior, where an escaped `Yield` is serialized by the lock.
+const CHECKED: bool = cfg!(all(debug_assertions, not(loom)));
+
+/// Access to the state handed off between a generator body and its consumer.
+pub trait SlotAccess<T> {
+    fn new(value: T) -> Self;
+
+    /// Run `poll`, which polls the generator body.
+    fn poll_body<R>(&self, poll: impl FnOnce() -> R) -> R;
+
+    /// Access the value from the generator body.
+    fn body<R>(&self, f: impl FnOnce(&mut T) -> R) -> R;
+
+    /// Access the value from the consumer.
+    fn consumer<R>(&self, f: impl FnOnce(&mut T) -> R) -> R;
+}
+
+pub struct Slot<T> {
+    value: UnsafeCell<T>,
+    locked: AtomicBool,
+    /// Set while the generator body is being polled.
+    polling: AtomicBool,
+}
+
+// SEAFTY: Every access to `value` holds the lock, just like `Mutex<T>`.
+unsafe impl<T: Send> Sync for Slot<T> {}
+
+impl<T> SlotAccess<T> for Slot<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            locked: AtomicBool::new(false),
+            polling: AtomicBool::new(false),
+        }
+    }
+
+    fn poll_body<R>(&self, poll: impl FnOnce() -> R) -> R {
+        struct Reset<'a>(&'a AtomicBool);
+        impl Drop for Reset<'_> {
+            fn drop(&mut self) {
+                self.0.store(false, Ordering::Relaxed);
+            }
+        }
+        if !CHECKED {
+            return poll();
+        }
+        self.polling.store(true, Ordering::Relaxed);
+        let _reset = Reset(&self.polling);
+        poll()
+    }
+
+    #[inline]
+    fn body<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        if CHECKED && !self.polling.load(Ordering::Relaxed) {
+            panic!("`Yield` is used outside of its generator body");
+        }
+        self.with(f)
+    }
+
+    #[inline]
+    fn consumer<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        if CHECKED && self.polling.load(Ordering::Relaxed) {
+            panic!("generator is accessed while its body is being polled");
+        }
+        self.with(f)
+    }
+}
+
+impl<T> Slot<T> {
+    #[inline]
+    fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        struct Unlock<'a>(&'a AtomicBool);
+        impl Drop for Unlock<'_> {
+            fn drop(&mut self) {
+                self.0.store(false, Ordering::Release);
+            }
+        }
+        while self
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            if CHECKED {
+                panic!(
+                    "concurrent access to generator state, `Yield` has escaped its generator body"
+                );
+            }
+            #[cfg(loom)]
+            loom::thread::yield_now();
+            #[cfg(not(loom))]
+            std::hint::spin_loop();
+        }
+        let _unlock = Unlock(&self.locked);
+        // SEAFTY: The lock is held, so this is unique access to `value`.
+        self.value.with_mut(|ptr| f(unsafe { &mut *ptr }))
+    }
+}
+
+/// Same as [`Slot`], but for generators that never leave their thread.
+pub struct LocalSlot<T> {
+    value: RefCell<T>,
+    /// Set while the generator body is being polled.
+    polling: Cell<bool>,
+}
+
+impl<T> SlotAccess<T> for LocalSlot<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value: RefCell::new(value),
+            polling: Cell::new(false),
+        }
+    }
+
+    fn poll_body<R>(&self, poll: impl FnOnce() -> R) -> R {
+        struct Reset<'a>(&'a Cell<bool>);
+        impl Drop for Reset<'_> {
+            fn drop(&mut self) {
+                self.0.set(false);
+            }
+        }
+        if !CHECKED {
+            return poll();
+        }
+        self.polling.set(true);
+        let _reset = Reset(&self.polling);
+        poll()
+    }
+
+    #[inline]
+    fn body<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        if CHECKED && !self.polling.get() {
+            panic!("`Yield` is used outside of its generator body");
+        }
+        f(&mut self.value.borrow_mut())
+    }
+
+    #[inline]
+    fn consumer<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        if CHECKED && self.polling.get() {
+            panic!("generator is accessed while its body is being polled");
+        }
+        f(&mut self.value.borrow_mut())
+    }
+}
+
+/// Same API as `loom::cell::UnsafeCell`.
+#[cfg(not(loom))]
+struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    #[inline]
+    fn new(value: T) -> Self {
+        Self(std::cell::UnsafeCell::new(value))
+    }
+
+    #[inline]
+    fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{AsyncGen, AsyncGenerator, GeneratorState, Return};
+use pin_project_lite::pin_project;
+use std::{
+    collections::VecDeque,
+    future::{poll_fn, Future},
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+struct Channel<Y, R> {
+    capacity: usize,
+    state: Mutex<State<Y, R>>,
+}
+
+struct State<Y, R> {
+    queue: VecDeque<Y>,
+    ret: Option<thread::Result<R>>,
+    producer: Option<Waker>,
+    consumer: Option<Waker>,
+    producer_dropped: bool,
+    consumer_dropped: bool,
+}
+
+impl<Y, R> Channel<Y, R> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(State {
+                queue: VecDeque::with_capacity(capacity),
+                ret: None,
+                producer: None,
+                consumer: None,
+                producer_dropped: false,
+                consumer_dropped: false,
+            }),
+        }
+    }
+}
+
+/// Sending half of the channel, it notifies the consumer when dropped.
+struct Sender<Y, R>(Arc<Channel<Y, R>>);
+
+impl<Y, R> Drop for Sender<Y, R> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        state.producer_dropped = true;
+        if let Some(waker) = state.consumer.take() {
+            waker.wake();
+        }
+    }
+}
+
+pin_project! {
+    /// A future that drives a generator, and sends its output to a [`Spawned`] handle.
+    ///
+    /// This `struct` is created by [`AsyncGen::spawn_buffered_with`], and should be
+    /// spawned onto a runtime. It completes once the generator completes (or panics),
+    /// or the [`Spawned`] handle is dropped.
+    pub struct Producer<G>
+    where
+        G: AsyncGenerator,
+    {
+        #[pin]
+        gen: G,
+        chan: Sender<G::Yield, G::Return>,
+    }
+}
+
+impl<G: AsyncGenerator> Future for Producer<G> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut me = self.project();
+        let chan = &me.chan.0;
+        loop {
+            {
+                let mut state = chan.state.lock().unwrap();
+                if state.consumer_dropped {
+                    return Poll::Ready(());
+                }
+                if state.queue.len() >= chan.capacity {
+                    state.producer = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+            // The generator is never polled again after a panic.
+            let poll = panic::catch_unwind(AssertUnwindSafe(|| me.gen.as_mut().poll_resume(cx)));
+
+            let mut state = chan.state.lock().unwrap();
+            match poll {
+                Ok(Poll::Pending) => {
+                    state.producer = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+                Ok(Poll::Ready(GeneratorState::Yielded(val))) => state.queue.push_back(val),
+                Ok(Poll::Ready(GeneratorState::Complete(val))) => state.ret = Some(Ok(val)),
+                Err(payload) => state.ret = Some(Err(payload)),
+            }
+            if let Some(waker) = state.consumer.take() {
+                waker.wake();
+            }
+            if state.ret.is_some() {
+                return Poll::Ready(());
+            }
+        }
+    }
+}
+
+/// A handle to a generator running on its own task.
+///
+/// Yielded values are delivered through a bounded channel, so the generator can
+/// run ahead of the consumer by up to `capacity` values. If the generator panics,
+/// the panic is propagated to the consumer when it reaches that point.
+///
+/// Dropping the handle cancels the task: the generator is dropped as soon as its task is polled again.
+///
+/// This `struct` is created by [`AsyncGen::spawn_buffered_with`], or by `AsyncGen::spawn_buffered`
+/// with the `tokio` feature.
+pub struct Spawned<Y, R = ()> {
+    chan: Arc<Channel<Y, R>>,
+    done: bool,
+}
+
+impl<Y, R> Spawned<Y, R> {
+    /// See [`AsyncGenerator::poll_resume`] for more details.
+    pub fn poll_resume(&mut self, cx: &mut Context<'_>) -> Poll<GeneratorState<Y, R>> {
+        assert!(!self.done, "`Spawned` resumed after completion");
+        let mut state = self.chan.state.lock().unwrap();
+        if let Some(val) = state.queue.pop_front() {
+            if let Some(waker) = state.producer.take() {
+                waker.wake();
+            }
+            return Poll::Ready(GeneratorState::Yielded(val));
+        }
+        match state.ret.take() {
+            Some(Ok(val)) => {
+                self.done = true;
+                Poll::Ready(GeneratorState::Complete(val))
+            }
+            Some(Err(payload)) => {
+                self.done = true;
+                drop(state);
+                panic::resume_unwind(payload)
+            }
+            None if state.producer_dropped => {
+                panic!("spawned generator was dropped before completion")
+            }
+            None => {
+                state.consumer = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /// See [`AsyncGenerator::poll_resume`] for more details.
+    #[inline]
+    pub async fn resume(&mut self) -> GeneratorState<Y, R> {
+        poll_fn(|cx| self.poll_resume(cx)).await
+    }
+}
+
+impl<Y, R> Drop for Spawned<Y, R> {
+    fn drop(&mut self) {
+        let mut state = self.chan.state.lock().unwrap();
+        state.consumer_dropped = true;
+        if let Some(waker) = state.producer.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<Y, R> AsyncGenerator for Spawned<Y, R> {
+    type Yield = Y;
+    type Return = R;
+
+    #[inline]
+    fn poll_resume(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        Spawned::poll_resume(self.get_mut(), cx)
+    }
+}
+
+impl<Y> futures_core::Stream for Spawned<Y> {
+    type Item = Y;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let me = self.get_mut();
+        if me.done {
+            return Poll::Ready(None);
+        }
+        me.poll_resume(cx).map(|s| match s {
+            GeneratorState::Yielded(val) => Some(val),
+            GeneratorState::Complete(()) => None,
+        })
+    }
+}
+
+impl<Fut, Y, E, R> AsyncGen<Fut, Y, E>
+where
+    Fut: Future<Output = Return<R>>,
+{
+    /// Run this generator on its own task, and consume it through a bounded channel.
+    ///
+    /// The generator is wrapped in a [`Producer`] future, and passed to `spawn`,
+    /// which should spawn it onto a runtime. This works with any runtime.
+    ///
+    /// See [`Spawned`] for more details.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use async_gen::gen;
+    /// use futures_util::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let g = gen! {
+    ///     for i in 0..3 {
+    ///         yield i;
+    ///     }
+    /// }
+    /// .spawn_buffered_with(8, |task| {
+    ///     tokio::spawn(task);
+    /// });
+    ///
+    /// assert_eq!(g.collect::<Vec<_>>().await, [0, 1, 2]);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn spawn_buffered_with<S>(self, capacity: usize, spawn: S) -> Spawned<Y, R>
+    where
+        S: FnOnce(Producer<Self>),
+    {
+        assert!(capacity > 0, "capacity must be greater than zero");
+        let chan = Arc::new(Channel::new(capacity));
+        spawn(Producer {
+            gen: self,
+            chan: Sender(chan.clone()),
+        });
+        Spawned { chan, done: false }
+    }
+
+    /// Same as [`AsyncGen::spawn_buffered_with`], but spawns onto the current tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero, or called from outside of a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_buffered(self, capacity: usize) -> Spawned<Y, R>
+    where
+        Self: Send + 'static,
+        Y: Send + 'static,
+        R: Send + 'static,
+    {
+        self.spawn_buffered_with(capacity, |task| {
+            tokio::spawn(task);
+        })
+    }
+}
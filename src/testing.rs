@@ -0,0 +1,221 @@
+//! Utilities for testing generators, without an async runtime.
+//!
+//! Most generators never truly pend, so they can be stepped with a waker that doesn't
+//! schedule anything. [`Stepper`] polls a generator once per [`Stepper::step`], and
+//! [`gate`] creates a future whose readiness is controlled by the test, so the exact
+//! order of yields and pendings can be checked deterministically.
+//!
+//! ## Examples
+//!
+//! ```
+//! use async_gen::{gen, testing::{gate, Stepper}, GeneratorState};
+//! use std::task::Poll;
+//!
+//! let (ready, opener) = gate();
+//! let mut g = Stepper::new(gen! {
+//!     yield 1;
+//!     ready.await;
+//!     yield 2;
+//! });
+//! assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded(1)));
+//! assert_eq!(g.step(), Poll::Pending);
+//! assert!(!g.is_woken());
+//!
+//! opener.open();
+//! assert!(g.is_woken());
+//! assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded(2)));
+//! assert_eq!(g.step(), Poll::Ready(GeneratorState::Complete(())));
+//! ```
+use crate::{AsyncGenerator, GeneratorState};
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+/// Steps a generator, one poll at a time.
+///
+/// The generator is polled with a waker that only records whether it was woken
+/// (See [`Stepper::is_woken`]).
+pub struct Stepper<G> {
+    gen: Pin<Box<G>>,
+    flag: Arc<Flag>,
+    waker: Waker,
+}
+
+#[derive(Default)]
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+impl<G: AsyncGenerator> Stepper<G> {
+    /// Create a stepper for the generator.
+    pub fn new(gen: G) -> Self {
+        let flag = Arc::new(Flag::default());
+        Self {
+            gen: Box::pin(gen),
+            waker: Waker::from(flag.clone()),
+            flag,
+        }
+    }
+
+    /// Poll the generator once.
+    pub fn step(&mut self) -> Poll<GeneratorState<G::Yield, G::Return>> {
+        self.flag.0.store(false, Ordering::Relaxed);
+        let mut cx = Context::from_waker(&self.waker);
+        self.gen.as_mut().poll_resume(&mut cx)
+    }
+
+    /// Returns `true` if the generator was woken, since the last [`Stepper::step`].
+    #[inline]
+    pub fn is_woken(&self) -> bool {
+        self.flag.0.load(Ordering::Relaxed)
+    }
+
+    /// Returns a pinned reference to the generator.
+    #[inline]
+    pub fn gen(&mut self) -> Pin<&mut G> {
+        self.gen.as_mut()
+    }
+}
+
+impl<G> fmt::Debug for Stepper<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stepper")
+            .field("is_woken", &self.flag.0.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Default)]
+struct GateState {
+    open: bool,
+    waker: Option<Waker>,
+}
+
+/// A future that is pending, until its [`Opener`] is opened.
+///
+/// This `struct` is created by [`gate()`].
+pub struct Gate {
+    state: Arc<Mutex<GateState>>,
+}
+
+/// Opens a [`Gate`].
+///
+/// This `struct` is created by [`gate()`].
+#[derive(Clone)]
+pub struct Opener {
+    state: Arc<Mutex<GateState>>,
+}
+
+/// Creates a [`Gate`] future, that becomes ready once [`Opener::open`] is called.
+///
+/// See the [module-level documentation](self) for an example.
+pub fn gate() -> (Gate, Opener) {
+    let state = Arc::new(Mutex::new(GateState::default()));
+    (
+        Gate {
+            state: state.clone(),
+        },
+        Opener { state },
+    )
+}
+
+impl Future for Gate {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.open {
+            return Poll::Ready(());
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Opener {
+    /// Open the gate, and wake the task that is waiting on it.
+    pub fn open(&self) {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            state.open = true;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl fmt::Debug for Gate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gate")
+            .field("open", &self.state.lock().unwrap().open)
+            .finish()
+    }
+}
+
+impl fmt::Debug for Opener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Opener")
+            .field("open", &self.state.lock().unwrap().open)
+            .finish()
+    }
+}
+
+/// Asserts that a generator yields the given values without pending, then completes.
+///
+/// The generator is stepped with [`testing::Stepper`](crate::testing::Stepper). Without
+/// `returns`, the generator must complete with `()`.
+///
+/// ## Examples
+///
+/// ```
+/// use async_gen::{assert_yields, gen};
+///
+/// assert_yields!(gen! { yield 1; yield 2; yield 3; }, [1, 2, 3]);
+/// assert_yields!(gen! { yield 'a'; return "done"; }, ['a'], returns = "done");
+/// ```
+///
+/// # Panics
+///
+/// Panics if the generator pends, or yields (or returns) a different value.
+#[macro_export]
+macro_rules! assert_yields {
+    ($gen:expr, [$($val:expr),* $(,)?] $(,)?) => {
+        $crate::assert_yields!($gen, [$($val),*], returns = ())
+    };
+    ($gen:expr, [$($val:expr),* $(,)?], returns = $ret:expr $(,)?) => {{
+        let mut stepper = $crate::testing::Stepper::new($gen);
+        let mut index = 0usize;
+        $(
+            match stepper.step() {
+                ::std::task::Poll::Ready($crate::GeneratorState::Yielded(val)) => {
+                    ::std::assert_eq!(val, $val, "unexpected value yielded at index {}", index);
+                }
+                state => ::std::panic!("expected a value at index {}, but got {:?}", index, state),
+            }
+            index += 1;
+        )*
+        match stepper.step() {
+            ::std::task::Poll::Ready($crate::GeneratorState::Complete(val)) => {
+                ::std::assert_eq!(val, $ret, "unexpected return value");
+            }
+            state => ::std::panic!("expected completion after {} values, but got {:?}", index, state),
+        }
+    }};
+}
@@ -0,0 +1,112 @@
+use crate::{AsyncGen, AsyncGenerator, GeneratorState};
+use pin_project_lite::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tracing::Span;
+
+pin_project! {
+    /// A generator, that is instrumented with a [`tracing`] span.
+    ///
+    /// The span is entered every time the generator is polled, so events emitted from
+    /// the generator body are recorded in its context. It also emits events when the
+    /// generator yields, completes, or is dropped before completion.
+    ///
+    /// This `struct` is created by [`AsyncGen::instrument`] or [`Traced::new`].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use async_gen::gen;
+    /// use futures_util::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let g = gen! {
+    ///     tracing::info!("start");
+    ///     yield 1;
+    ///     yield 2;
+    /// }
+    /// .instrument(tracing::info_span!("numbers"));
+    ///
+    /// let v: Vec<_> = g.collect().await;
+    /// assert_eq!(v, [1, 2]);
+    /// # }
+    /// ```
+    pub struct Traced<G> {
+        #[pin]
+        gen: G,
+        span: Span,
+        yields: u64,
+        complete: bool,
+    }
+
+    impl<G> PinnedDrop for Traced<G> {
+        fn drop(this: Pin<&mut Self>) {
+            let me = this.project();
+            if !*me.complete {
+                let _enter = me.span.enter();
+                tracing::debug!(yields = *me.yields, "generator dropped before completion");
+            }
+        }
+    }
+}
+
+impl<G> Traced<G> {
+    /// Instrument the generator with the given `span`.
+    #[inline]
+    pub fn new(gen: G, span: Span) -> Self {
+        Self {
+            gen,
+            span,
+            yields: 0,
+            complete: false,
+        }
+    }
+
+    /// Returns the span, that this generator is instrumented with.
+    #[inline]
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+impl<G: AsyncGenerator> AsyncGenerator for Traced<G> {
+    type Yield = G::Yield;
+    type Return = G::Return;
+
+    fn poll_resume(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        let me = self.project();
+        let _enter = me.span.enter();
+        let state = me.gen.poll_resume(cx);
+        match state {
+            Poll::Ready(GeneratorState::Yielded(_)) => {
+                *me.yields += 1;
+                tracing::trace!(yields = *me.yields, "generator yielded");
+            }
+            Poll::Ready(GeneratorState::Complete(_)) => {
+                *me.complete = true;
+                tracing::debug!(yields = *me.yields, "generator completed");
+            }
+            Poll::Pending => {}
+        }
+        state
+    }
+}
+
+impl_stream!(impl<G> Traced<G> where G: AsyncGenerator<Return = ()>);
+
+impl<Fut: Future, Y, E> AsyncGen<Fut, Y, E> {
+    /// Instrument this generator with a [`tracing`] span.
+    ///
+    /// See [`Traced`] for more details.
+    #[inline]
+    pub fn instrument(self, span: Span) -> Traced<Self> {
+        Traced::new(self, span)
+    }
+}
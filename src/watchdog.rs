@@ -0,0 +1,229 @@
+//! Detect stalled generators.
+//!
+//! [`Watchdog`] reports a [`Stall`] when the consumer doesn't resume a generator,
+//! or its body stays pending, for longer than the [`Thresholds`]. A [`Clock`] can be
+//! provided, to check stalls deterministically in tests.
+use crate::{AsyncGenerator, GeneratorState};
+use pin_project_lite::pin_project;
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// A source of time, used by [`Watchdog`].
+///
+/// Implement this trait to use a mock clock in tests.
+pub trait Clock {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+}
+
+/// A [`Clock`] backed by [`Instant::now`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Stall thresholds of a [`Watchdog`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Thresholds {
+    /// How long a yielded value can wait for the consumer to resume the generator.
+    pub consumer: Duration,
+    /// How long the generator body can stay pending (for example, waiting on I/O).
+    pub pending: Duration,
+}
+
+impl Thresholds {
+    /// Use the same `threshold` for both kind of stalls.
+    #[inline]
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            consumer: threshold,
+            pending: threshold,
+        }
+    }
+}
+
+/// A stall reported by [`Watchdog`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stall {
+    /// A yielded value was not taken by the consumer, for the given duration.
+    Consumer(Duration),
+    /// The generator body stayed pending, for the given duration.
+    Pending(Duration),
+}
+
+struct Shared<F, C> {
+    clock: C,
+    thresholds: Thresholds,
+    on_stall: F,
+    suspended: Mutex<Suspended>,
+}
+
+#[derive(Default)]
+struct Suspended {
+    // When the generator returned `Pending` or `Yielded` (`true` if yielded).
+    since: Option<(Instant, bool)>,
+    reported: bool,
+}
+
+impl<F: Fn(Stall), C: Clock> Shared<F, C> {
+    /// Report the current stall, if it exceeds the threshold and wasn't reported yet.
+    fn check(&self, resumed: bool) {
+        let now = self.clock.now();
+        let stall = {
+            let mut suspended = self.suspended.lock().unwrap();
+            let stall = match suspended.since {
+                Some((at, true)) if now - at > self.thresholds.consumer => {
+                    Some(Stall::Consumer(now - at))
+                }
+                Some((at, false)) if now - at > self.thresholds.pending => {
+                    Some(Stall::Pending(now - at))
+                }
+                _ => None,
+            }
+            .filter(|_| !suspended.reported);
+
+            if resumed {
+                *suspended = Suspended::default();
+            } else if stall.is_some() {
+                suspended.reported = true;
+            }
+            stall
+        };
+        if let Some(stall) = stall {
+            (self.on_stall)(stall);
+        }
+    }
+
+    fn suspend(&self, yielded: bool) {
+        *self.suspended.lock().unwrap() = Suspended {
+            since: Some((self.clock.now(), yielded)),
+            reported: false,
+        };
+    }
+}
+
+pin_project! {
+    /// A generator wrapper, that reports stalls through a callback.
+    ///
+    /// A stall is reported when a yielded value sits untaken by the consumer, or
+    /// the generator body stays pending, for longer than the [`Thresholds`].
+    ///
+    /// Stalls are checked when the generator is polled again, so a stall is reported
+    /// (at most once) with its total duration after it ends. To detect ongoing stalls,
+    /// call [`WatchdogHandle::check`] periodically, for example from a timer task.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::{pin::pin, time::Duration};
+    /// use async_gen::{
+    ///     gen,
+    ///     watchdog::{Stall, Thresholds, Watchdog},
+    /// };
+    /// use futures_util::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let thresholds = Thresholds::new(Duration::from_millis(10));
+    /// let mut g = pin!(Watchdog::new(gen! { yield 1; }, thresholds, |stall| {
+    ///     assert!(matches!(stall, Stall::Consumer(_)));
+    /// }));
+    /// assert_eq!(g.next().await, Some(1));
+    /// tokio::time::sleep(Duration::from_millis(20)).await;
+    /// assert_eq!(g.next().await, None);
+    /// # }
+    /// ```
+    pub struct Watchdog<G, F, C = SystemClock> {
+        #[pin]
+        gen: G,
+        shared: Arc<Shared<F, C>>,
+    }
+}
+
+impl<G, F: Fn(Stall)> Watchdog<G, F> {
+    /// Watch the generator for stalls, and call `on_stall` for each stall.
+    #[inline]
+    pub fn new(gen: G, thresholds: Thresholds, on_stall: F) -> Self {
+        Self::with_clock(gen, thresholds, SystemClock, on_stall)
+    }
+}
+
+impl<G, F: Fn(Stall), C: Clock> Watchdog<G, F, C> {
+    /// Same as [`Watchdog::new`], but time comes from the given `clock`.
+    pub fn with_clock(gen: G, thresholds: Thresholds, clock: C, on_stall: F) -> Self {
+        Self {
+            gen,
+            shared: Arc::new(Shared {
+                clock,
+                thresholds,
+                on_stall,
+                suspended: Mutex::default(),
+            }),
+        }
+    }
+
+    /// Returns a handle, that can check for ongoing stalls from elsewhere.
+    #[inline]
+    pub fn handle(&self) -> WatchdogHandle<F, C> {
+        WatchdogHandle {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+/// A handle to check a [`Watchdog`] for ongoing stalls.
+///
+/// This `struct` is created by [`Watchdog::handle`].
+pub struct WatchdogHandle<F, C = SystemClock> {
+    shared: Arc<Shared<F, C>>,
+}
+
+impl<F, C> Clone for WatchdogHandle<F, C> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<F: Fn(Stall), C: Clock> WatchdogHandle<F, C> {
+    /// Check whether the generator is currently stalled.
+    ///
+    /// If so, the stall is reported through the callback (at most once per stall).
+    #[inline]
+    pub fn check(&self) {
+        self.shared.check(false);
+    }
+}
+
+impl<G: AsyncGenerator, F: Fn(Stall), C: Clock> AsyncGenerator for Watchdog<G, F, C> {
+    type Yield = G::Yield;
+    type Return = G::Return;
+
+    fn poll_resume(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<GeneratorState<Self::Yield, Self::Return>> {
+        let me = self.project();
+        me.shared.check(true);
+        let state = me.gen.poll_resume(cx);
+        match state {
+            Poll::Ready(GeneratorState::Yielded(_)) => me.shared.suspend(true),
+            Poll::Pending => me.shared.suspend(false),
+            Poll::Ready(GeneratorState::Complete(_)) => {}
+        }
+        state
+    }
+}
+
+impl_stream!(impl<G, F, C> Watchdog<G, F, C> where G: AsyncGenerator<Return = ()>, F: Fn(Stall), C: Clock);
@@ -0,0 +1,43 @@
+use async_gen::{gen, GeneratorState};
+use std::pin::pin;
+
+#[tokio::test]
+async fn yield_many_then_resume() {
+    let mut g = pin!(gen(|mut c| async move {
+        c.yield_many(0..3).await;
+        c.yield_(3).await;
+        c.return_("done")
+    }));
+    for i in 0..4 {
+        assert_eq!(g.resume().await, GeneratorState::Yielded(i));
+    }
+    assert_eq!(g.resume().await, GeneratorState::Complete("done"));
+}
+
+#[tokio::test]
+async fn next_batch_respects_max() {
+    let mut g = pin!(gen(|mut c| async move {
+        c.yield_many(0..5).await;
+        c.yield_(5).await;
+        c.return_(())
+    }));
+    let mut buf = Vec::new();
+    assert_eq!(g.next_batch(&mut buf, 3).await, Some(3));
+    assert_eq!(g.next_batch(&mut buf, 3).await, Some(2));
+    assert_eq!(g.next_batch(&mut buf, 3).await, Some(1));
+    assert_eq!(g.next_batch(&mut buf, 3).await, None);
+    assert_eq!(buf, [0, 1, 2, 3, 4, 5]);
+}
+
+#[tokio::test]
+async fn next_batch_with_prefetch() {
+    let mut g = pin!(gen! {
+        for i in 0..10 {
+            yield i;
+        }
+    }
+    .prefetch(4));
+    let mut buf = Vec::new();
+    assert_eq!(g.next_batch(&mut buf, 16).await, Some(4));
+    assert_eq!(buf, [0, 1, 2, 3]);
+}
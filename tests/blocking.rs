@@ -0,0 +1,97 @@
+//! Same cases as `stream.rs`, that don't need a runtime, but run with the blocking API.
+use async_gen::{gen, AsyncGenerator, BlockingIter, GeneratorState};
+use std::{pin::pin, thread, time::Duration};
+
+#[test]
+fn noop_stream() {
+    let mut gen = pin!(gen! {});
+    assert_eq!(gen.resume_blocking(), GeneratorState::Complete(()));
+}
+
+#[test]
+fn empty_stream() {
+    let mut ran = false;
+    {
+        let r = &mut ran;
+        let mut gen = pin!(gen! {
+            *r = true;
+            println!("hello world!");
+        });
+        assert_eq!(gen.resume_blocking(), GeneratorState::Complete(()));
+    }
+    assert!(ran);
+}
+
+#[test]
+fn return_stream() {
+    fn build_stream() -> impl AsyncGenerator<Yield = i32, Return = ()> {
+        gen! {
+            yield 1;
+            yield 2;
+            yield 3;
+        }
+    }
+    let s = BlockingIter::new(build_stream());
+
+    let values: Vec<_> = s.collect();
+    assert_eq!(3, values.len());
+    assert_eq!(1, values[0]);
+    assert_eq!(2, values[1]);
+    assert_eq!(3, values[2]);
+}
+
+#[test]
+fn consume_channel() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let sender = thread::spawn(move || {
+        for i in 0..3 {
+            thread::sleep(Duration::from_millis(10));
+            tx.blocking_send(i).unwrap();
+        }
+    });
+    let values: Vec<_> = gen! {
+        while let Some(v) = rx.recv().await {
+            yield v;
+        }
+    }
+    .into_blocking_iter()
+    .collect();
+    sender.join().unwrap();
+    assert_eq!(values, [0, 1, 2]);
+}
+
+#[test]
+fn borrow_self() {
+    struct Data(String);
+
+    impl Data {
+        fn stream<'a>(&'a self) -> impl AsyncGenerator<Yield = &'a str, Return = ()> + 'a {
+            gen! {
+                yield &self.0[..];
+            }
+        }
+    }
+
+    let data = Data("hello".to_string());
+    let mut s = BlockingIter::new(data.stream());
+    assert_eq!(Some("hello"), s.next());
+}
+
+#[test]
+fn yield_non_unpin_value() {
+    let s: Vec<_> = gen! {
+        for i in 0..3 {
+            yield async move { i };
+        }
+    }
+    .into_blocking_iter()
+    .map(|fut| {
+        let mut g = pin!(gen! {
+            return fut.await;
+        });
+        g.resume_blocking().complete().unwrap()
+    })
+    .collect();
+
+    assert_eq!(s, vec![0, 1, 2]);
+}
@@ -0,0 +1,147 @@
+use async_gen::{gen, testing::Stepper, BlockingIter, GeneratorState, Overflow};
+use std::{
+    cell::RefCell,
+    panic::{catch_unwind, AssertUnwindSafe},
+    rc::Rc,
+    sync::Arc,
+    task::Poll,
+    thread,
+};
+
+#[test]
+fn slowest_subscriber_controls_backpressure() {
+    let a = gen! {
+        for i in 0..4 {
+            yield i;
+        }
+    }
+    .broadcast(2);
+    let b = a.clone();
+    let (mut a, mut b) = (Stepper::new(a), Stepper::new(b));
+
+    assert_eq!(a.step(), Poll::Ready(GeneratorState::Yielded(0)));
+    assert_eq!(a.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    // `b` hasn't received any value yet, so the buffer is full.
+    assert_eq!(a.step(), Poll::Pending);
+
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(0)));
+    assert!(a.is_woken());
+    assert_eq!(a.step(), Poll::Ready(GeneratorState::Yielded(2)));
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(2)));
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(3)));
+    assert_eq!(a.step(), Poll::Ready(GeneratorState::Yielded(3)));
+}
+
+#[test]
+fn drop_oldest_skips_values() {
+    let a = gen! {
+        for i in 0..5 {
+            yield i;
+        }
+    }
+    .broadcast_with(2, Overflow::DropOldest);
+    let b = a.clone();
+    let fast: Vec<_> = BlockingIter::new(a).collect();
+    assert_eq!(fast, [0, 1, 2, 3, 4]);
+
+    let mut b = Stepper::new(b);
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(3)));
+    assert_eq!(b.gen().lagged(), 3);
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(4)));
+    assert!(matches!(b.step(), Poll::Ready(GeneratorState::Complete(_))));
+}
+
+#[test]
+fn every_subscriber_shares_return_value() {
+    let a = gen! {
+        yield 1;
+        return String::from("done");
+    }
+    .broadcast(1);
+    let b = a.clone();
+    let (mut a, mut b) = (Stepper::new(a), Stepper::new(b));
+
+    assert_eq!(a.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    let Poll::Ready(GeneratorState::Complete(x)) = a.step() else {
+        panic!("expected completion");
+    };
+    let Poll::Ready(GeneratorState::Complete(y)) = b.step() else {
+        panic!("expected completion");
+    };
+    assert_eq!(*x, "done");
+    assert!(Arc::ptr_eq(&x, &y));
+    assert!(Arc::ptr_eq(&x, &b.gen().return_value().unwrap()));
+}
+
+#[test]
+fn subscribers_across_threads() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    let subscriber = gen! {
+        while let Some(v) = rx.recv().await {
+            yield v;
+        }
+    }
+    .broadcast(4);
+    let handles: Vec<_> = (0..3)
+        .map(|_| {
+            let s = subscriber.clone();
+            thread::spawn(move || BlockingIter::new(s).collect::<Vec<_>>())
+        })
+        .collect();
+    drop(subscriber);
+    for i in 0..100 {
+        tx.blocking_send(i).unwrap();
+    }
+    drop(tx);
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), (0..100).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn panic_in_body() {
+    let a = gen! {
+        yield 1;
+        panic!("boom");
+    }
+    .broadcast(2);
+    let b = a.clone();
+    let (mut a, mut b) = (Stepper::new(a), Stepper::new(b));
+
+    assert_eq!(a.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert!(catch_unwind(AssertUnwindSafe(|| a.step())).is_err());
+    // The buffered value is still received, then the panic is observed.
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert!(catch_unwind(AssertUnwindSafe(|| b.step())).is_err());
+    assert!(b.gen().return_value().is_none());
+    // Subscribers are still dropped cleanly.
+    drop((a, b.gen().clone(), b));
+}
+
+#[test]
+fn body_uses_its_own_subscribers() {
+    let hook = Rc::new(RefCell::new(None::<Box<dyn Fn()>>));
+    let body_hook = hook.clone();
+    let a = gen! {
+        yield 1;
+        if let Some(f) = &*body_hook.borrow() {
+            f();
+        }
+        yield 2;
+    }
+    // `b` never receives a value, so every value is buffered.
+    .broadcast(4);
+    let b = a.clone();
+    *hook.borrow_mut() = Some(Box::new(move || {
+        assert!(b.return_value().is_none());
+        drop(b.clone());
+    }));
+
+    let mut it = BlockingIter::new(a);
+    assert_eq!(it.by_ref().collect::<Vec<_>>(), [1, 2]);
+    assert!(it.return_value().is_some());
+    // Break the cycle, the hook owns a subscriber.
+    hook.borrow_mut().take();
+}
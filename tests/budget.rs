@@ -0,0 +1,75 @@
+use async_gen::{gen, GeneratorState};
+use std::{
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+#[derive(Default)]
+struct CountWake(AtomicUsize);
+
+impl Wake for CountWake {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn yield_to_executor_after_budget() {
+    let wakes = Arc::new(CountWake::default());
+    let waker = Waker::from(wakes.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let mut g = pin!(gen! {
+        for i in 0..3 {
+            yield i;
+        }
+    }
+    .with_budget(2));
+
+    assert_eq!(
+        g.as_mut().poll_resume(&mut cx),
+        Poll::Ready(GeneratorState::Yielded(0))
+    );
+    assert_eq!(
+        g.as_mut().poll_resume(&mut cx),
+        Poll::Ready(GeneratorState::Yielded(1))
+    );
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 0);
+
+    assert_eq!(g.as_mut().poll_resume(&mut cx), Poll::Pending);
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+
+    assert_eq!(
+        g.as_mut().poll_resume(&mut cx),
+        Poll::Ready(GeneratorState::Yielded(2))
+    );
+    assert_eq!(
+        g.as_mut().poll_resume(&mut cx),
+        Poll::Ready(GeneratorState::Complete(()))
+    );
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn tokio_coop_budget() {
+    let mut g = pin!(gen! {
+        for i in 0..1000 {
+            yield i;
+        }
+    }
+    .with_coop_budget());
+
+    let ready = std::future::poll_fn(|cx| {
+        let mut ready = 0;
+        while g.as_mut().poll_resume(cx).is_ready() {
+            ready += 1;
+        }
+        Poll::Ready(ready)
+    })
+    .await;
+    assert!(ready < 1000);
+}
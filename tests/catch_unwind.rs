@@ -0,0 +1,55 @@
+use async_gen::{gen, testing::Stepper, CatchUnwind, GeneratorState};
+use futures_util::StreamExt;
+use std::{cell::Cell, panic::AssertUnwindSafe, task::Poll};
+
+#[tokio::test]
+async fn panic_as_last_item() {
+    let g = gen! {
+        yield 1;
+        yield 2;
+        panic!("boom");
+    }
+    .catch_unwind();
+
+    let items: Vec<_> = g.collect().await;
+    assert_eq!(items.len(), 3);
+    assert_eq!(*items[0].as_ref().unwrap(), 1);
+    assert_eq!(*items[1].as_ref().unwrap(), 2);
+    let payload = items[2].as_ref().unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+}
+
+#[test]
+fn complete_without_panic() {
+    let mut g = Stepper::new(
+        gen! {
+            yield 1;
+            return "done";
+        }
+        .catch_unwind(),
+    );
+    assert!(matches!(g.step(), Poll::Ready(GeneratorState::Yielded(1))));
+    assert!(matches!(
+        g.step(),
+        Poll::Ready(GeneratorState::Complete(Ok("done")))
+    ));
+}
+
+#[test]
+#[should_panic(expected = "`CatchUnwind` resumed after the generator panicked")]
+fn resume_after_panic() {
+    let polls = Cell::new(0);
+    let counter = &polls;
+    let mut g = Stepper::new(CatchUnwind::new(AssertUnwindSafe(gen! {
+        counter.set(counter.get() + 1);
+        yield 1;
+        panic!("boom");
+    })));
+    assert!(matches!(g.step(), Poll::Ready(GeneratorState::Yielded(1))));
+    assert!(matches!(
+        g.step(),
+        Poll::Ready(GeneratorState::Complete(Err(_)))
+    ));
+    assert_eq!(polls.get(), 1);
+    let _ = g.step();
+}
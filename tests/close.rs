@@ -0,0 +1,69 @@
+use async_gen::{gen, GeneratorState};
+use std::pin::pin;
+
+#[tokio::test]
+async fn close_runs_async_cleanup() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+    {
+        let mut g = pin!(gen(|mut c| async move {
+            for i in 0..10 {
+                c.yield_(i).await;
+                if c.is_closing() {
+                    break;
+                }
+            }
+            tokio::task::yield_now().await;
+            tx.send("cleanup").await.unwrap();
+            c.return_("closed")
+        }));
+        assert_eq!(g.resume().await, GeneratorState::Yielded(0));
+        assert_eq!(g.close().await, "closed");
+    }
+    assert_eq!(rx.recv().await, Some("cleanup"));
+}
+
+#[tokio::test]
+async fn close_after_last_yield() {
+    let mut g = pin!(gen! {
+        yield 1;
+        tokio::task::yield_now().await;
+        return "done";
+    });
+    assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    assert_eq!(g.close().await, "done");
+}
+
+#[tokio::test]
+async fn close_not_started() {
+    let mut ran = false;
+    {
+        let r = &mut ran;
+        let mut g = pin!(gen! {
+            yield 1;
+            yield 2;
+            *r = true;
+            return "done";
+        });
+        assert_eq!(g.close().await, "done");
+    }
+    assert!(ran);
+}
+
+#[tokio::test]
+async fn close_mid_stream() {
+    let mut g = pin!(gen! {
+        let mut sum = 0;
+        for i in 1.. {
+            let resumed = yield i;
+            sum += i;
+            if resumed.is_closing() {
+                break;
+            }
+        }
+        yield -1;
+        return sum;
+    });
+    assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    assert_eq!(g.resume().await, GeneratorState::Yielded(2));
+    assert_eq!(g.close().await, 3);
+}
@@ -0,0 +1,71 @@
+use async_gen::{gen_fn, GenFn, GeneratorState, IntoAsyncGenerator};
+use futures_util::StreamExt;
+use std::{
+    pin::pin,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+#[tokio::test]
+async fn borrow_arguments() {
+    let split = gen_fn!(|text: &str, sep: char| {
+        for part in text.split(sep) {
+            yield part;
+        }
+        return text.len();
+    });
+    let text = String::from("a,b");
+    let mut g = pin!(split.call((&text, ',')));
+    assert_eq!(g.resume().await, GeneratorState::Yielded("a"));
+    assert_eq!(g.resume().await, GeneratorState::Yielded("b"));
+    assert_eq!(g.resume().await, GeneratorState::Complete(3));
+
+    let mut parts = split.call(("x y z", ' ')).into_blocking_iter();
+    assert_eq!(parts.by_ref().collect::<Vec<_>>(), ["x", "y", "z"]);
+    assert_eq!(parts.into_return(), Some(5));
+}
+
+#[tokio::test]
+async fn retry_from_the_beginning() {
+    let attempts = AtomicU32::new(0);
+    let attempts = &attempts;
+    let fetch = gen_fn!(|| {
+        let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+        yield 1;
+        if attempt == 0 {
+            return Err("connection reset");
+        }
+        yield 2;
+        Ok(())
+    });
+
+    let result = loop {
+        let mut g = pin!((&fetch).into_async_gen());
+        let mut values = Vec::new();
+        let ret = loop {
+            match g.resume().await {
+                GeneratorState::Yielded(v) => values.push(v),
+                GeneratorState::Complete(ret) => break ret,
+            }
+        };
+        if ret.is_ok() {
+            break values;
+        }
+    };
+    assert_eq!(result, [1, 2]);
+    assert_eq!(attempts.load(Ordering::Relaxed), 2);
+}
+
+#[tokio::test]
+async fn from_function() {
+    let squares = GenFn::new(|(n,): (u32,)| {
+        async_gen::gen! {
+            for i in 0..n {
+                yield i * i;
+            }
+        }
+    });
+    let a: Vec<_> = squares.call((3,)).collect().await;
+    let b: Vec<_> = squares.call((3,)).collect().await;
+    assert_eq!(a, [0, 1, 4]);
+    assert_eq!(a, b);
+}
@@ -0,0 +1,80 @@
+use async_gen::{gen, testing::Stepper, GeneratorState};
+use std::{ops::ControlFlow, task::Poll};
+
+#[test]
+fn combinators() {
+    let mut state: GeneratorState<i32, &str> = GeneratorState::Yielded(1);
+    assert!(state.is_yielded());
+    if let GeneratorState::Yielded(v) = state.as_mut() {
+        *v += 1;
+    }
+    assert_eq!(state.as_ref(), GeneratorState::Yielded(&2));
+    assert_eq!(
+        state.map_yielded(|v| v.to_string()).yielded(),
+        Some("2".to_string())
+    );
+
+    let state: GeneratorState<i32, &str> = GeneratorState::Complete("done");
+    assert!(state.is_complete());
+    assert_eq!(state.yielded(), None);
+    assert_eq!(state.map_complete(str::len).complete(), Some(4));
+}
+
+#[test]
+fn conversions() {
+    let state: GeneratorState<i32, ()> = GeneratorState::Yielded(1);
+    assert_eq!(Result::from(state), Ok(1));
+    assert_eq!(ControlFlow::from(state), ControlFlow::Continue(1));
+
+    let state: GeneratorState<i32, &str> = GeneratorState::Complete("done");
+    assert_eq!(Result::from(state), Err("done"));
+    assert_eq!(ControlFlow::from(state), ControlFlow::Break("done"));
+    assert_eq!(GeneratorState::from(Err::<i32, _>("done")), state);
+    assert_eq!(
+        GeneratorState::from(ControlFlow::Break::<_, i32>("done")),
+        state
+    );
+
+    // `?` returns early, once the generator has completed.
+    fn double(state: GeneratorState<i32, &str>) -> Result<i32, &str> {
+        let val = Result::from(state)?;
+        Ok(val * 2)
+    }
+    assert_eq!(double(GeneratorState::Yielded(2)), Ok(4));
+    assert_eq!(double(state), Err("done"));
+}
+
+#[test]
+fn transpose() {
+    let mut g = Stepper::new(gen! {
+        yield Some(1);
+        yield None;
+        return "done";
+    });
+    let states: Vec<_> = (0..3)
+        .map(|_| match g.step() {
+            Poll::Ready(state) => state.transpose(),
+            Poll::Pending => unreachable!(),
+        })
+        .collect();
+    assert_eq!(
+        states,
+        [
+            Some(GeneratorState::Yielded(1)),
+            None,
+            Some(GeneratorState::Complete("done"))
+        ]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let state: GeneratorState<i32, String> = GeneratorState::Complete("done".into());
+    let json = serde_json::to_string(&state).unwrap();
+    assert_eq!(json, r#"{"Complete":"done"}"#);
+    assert_eq!(
+        serde_json::from_str::<GeneratorState<i32, String>>(&json).unwrap(),
+        state
+    );
+}
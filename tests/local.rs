@@ -0,0 +1,83 @@
+use async_gen::{local_gen, GeneratorState};
+use futures_util::StreamExt;
+use std::{cell::RefCell, pin::pin, rc::Rc};
+use tokio::task::LocalSet;
+
+#[tokio::test]
+async fn hold_rc_across_yield() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let shared = log.clone();
+    let g = local_gen! {
+        let local = Rc::new(RefCell::new(0));
+        for i in 0..3 {
+            *local.borrow_mut() += i;
+            shared.borrow_mut().push(*local.borrow());
+            yield i;
+        }
+        return *local.borrow();
+    };
+
+    let mut g = pin!(g);
+    for i in 0..3 {
+        assert_eq!(g.resume().await, GeneratorState::Yielded(i));
+    }
+    assert_eq!(g.resume().await, GeneratorState::Complete(3));
+    assert_eq!(*log.borrow(), [0, 1, 3]);
+}
+
+#[tokio::test]
+async fn stream_on_local_set() {
+    let local = LocalSet::new();
+    let sum = local
+        .run_until(async {
+            let values = Rc::new([1, 2, 3]);
+            tokio::task::spawn_local(async move {
+                let g = local_gen! {
+                    for v in values.iter() {
+                        tokio::task::yield_now().await;
+                        yield *v;
+                    }
+                };
+                g.fold(0, |acc, v| async move { acc + v }).await
+            })
+            .await
+            .unwrap()
+        })
+        .await;
+    assert_eq!(sum, 6);
+}
+
+#[tokio::test]
+async fn throw_and_close() {
+    let mut g = pin!(local_gen! {
+        yield? 1;
+        yield? 2;
+        Ok::<_, &str>(())
+    });
+    assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    assert_eq!(
+        g.throw("failed").await,
+        GeneratorState::Complete(Err("failed"))
+    );
+
+    let mut g = pin!(local_gen! {
+        yield 1;
+        return 2;
+    });
+    assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    assert_eq!(g.close().await, 2);
+}
+
+#[tokio::test]
+async fn next_batch() {
+    let mut g = pin!(local_gen(|mut c| async move {
+        let values = Rc::new(vec![1, 2, 3]);
+        c.yield_many(values.iter().copied()).await;
+        c.return_(())
+    }));
+    let mut batch = Vec::new();
+    assert_eq!(g.next_batch(&mut batch, 2).await, Some(2));
+    assert_eq!(g.next_batch(&mut batch, 2).await, Some(1));
+    assert_eq!(g.next_batch(&mut batch, 2).await, None);
+    assert_eq!(batch, [1, 2, 3]);
+}
@@ -0,0 +1,64 @@
+//! RUSTFLAGS="--cfg loom" cargo test --test loom --release
+#![cfg(loom)]
+
+use async_gen::{gen, AsyncGenerator, GeneratorState, Return, Yield};
+use futures_util::task::noop_waker_ref;
+use loom::thread;
+use std::{
+    future::{pending, Future},
+    pin::{pin, Pin},
+    task::{Context, Poll},
+};
+
+fn poll<F: Future>(fut: Pin<&mut F>) -> Poll<F::Output> {
+    fut.poll(&mut Context::from_waker(noop_waker_ref()))
+}
+
+fn resume<G: AsyncGenerator>(g: Pin<&mut G>) -> Poll<GeneratorState<G::Yield, G::Return>> {
+    g.poll_resume(&mut Context::from_waker(noop_waker_ref()))
+}
+
+#[test]
+fn handoff_across_threads() {
+    loom::model(|| {
+        let mut g = Box::pin(gen! {
+            yield 1;
+            yield 2;
+        });
+        assert_eq!(resume(g.as_mut()), Poll::Ready(GeneratorState::Yielded(1)));
+        thread::spawn(move || {
+            assert_eq!(resume(g.as_mut()), Poll::Ready(GeneratorState::Yielded(2)));
+            assert_eq!(
+                resume(g.as_mut()),
+                Poll::Ready(GeneratorState::Complete(()))
+            );
+        })
+        .join()
+        .unwrap();
+    });
+}
+
+#[test]
+fn escaped_yield_is_serialized() {
+    loom::model(|| {
+        let mut escaped = None::<Yield<i32>>;
+        let mut g = Box::pin(gen(|c| {
+            escaped = Some(c);
+            pending::<Return>()
+        }));
+        let mut c = escaped.unwrap();
+
+        let producer = thread::spawn(move || {
+            let _ = poll(pin!(c.yield_(1)));
+        });
+        let first = resume(g.as_mut());
+        producer.join().unwrap();
+
+        match first {
+            Poll::Ready(val) => assert_eq!(val, GeneratorState::Yielded(1)),
+            Poll::Pending => {
+                assert_eq!(resume(g.as_mut()), Poll::Ready(GeneratorState::Yielded(1)))
+            }
+        }
+    });
+}
@@ -0,0 +1,98 @@
+use async_gen::{
+    gen,
+    testing::{gate, Stepper},
+    BlockingIter, GeneratorState,
+};
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::Poll,
+};
+
+#[test]
+fn body_runs_once() {
+    let runs = Arc::new(AtomicUsize::new(0));
+    let counter = runs.clone();
+    let scan = gen! {
+        counter.fetch_add(1, Ordering::Relaxed);
+        yield 1;
+        yield 2;
+        return "done";
+    }
+    .memoize();
+
+    for _ in 0..3 {
+        let mut it = BlockingIter::new(scan.clone());
+        assert_eq!(it.by_ref().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(*it.into_return().unwrap(), "done");
+    }
+    assert_eq!(runs.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn late_consumer_joins_live_tail() {
+    let (ready, opener) = gate();
+    let a = gen! {
+        yield 1;
+        ready.await;
+        yield 2;
+    }
+    .memoize();
+    let b = a.clone();
+    let (mut a, mut b) = (Stepper::new(a), Stepper::new(b));
+
+    assert_eq!(a.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert_eq!(a.step(), Poll::Pending);
+    // Replays the cached value, then waits for the live tail.
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert_eq!(b.step(), Poll::Pending);
+
+    opener.open();
+    assert!(a.is_woken());
+    assert!(b.is_woken());
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(2)));
+    assert_eq!(a.step(), Poll::Ready(GeneratorState::Yielded(2)));
+}
+
+#[test]
+fn every_consumer_shares_return_value() {
+    let a = gen! {
+        yield 0;
+        return vec![1, 2, 3];
+    }
+    .memoize();
+    let b = a.clone();
+    let mut x = BlockingIter::new(a);
+    let mut y = BlockingIter::new(b);
+    assert_eq!(x.by_ref().count(), 1);
+    assert_eq!(y.by_ref().count(), 1);
+    assert_eq!(**x.return_value().unwrap(), [1, 2, 3]);
+    assert!(Arc::ptr_eq(
+        x.return_value().unwrap(),
+        y.return_value().unwrap()
+    ));
+}
+
+#[test]
+fn panic_in_body() {
+    let a = gen! {
+        yield 1;
+        panic!("boom");
+    }
+    .memoize();
+    let b = a.clone();
+    let (mut a, mut b) = (Stepper::new(a), Stepper::new(b));
+
+    assert_eq!(a.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert!(catch_unwind(AssertUnwindSafe(|| a.step())).is_err());
+    // The cached value is still replayed, then the panic is observed.
+    assert_eq!(b.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert!(catch_unwind(AssertUnwindSafe(|| b.step())).is_err());
+    // A late handle replays the cache too.
+    let mut c = Stepper::new(b.gen().clone());
+    assert_eq!(c.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    drop((a, b, c));
+}
@@ -0,0 +1,103 @@
+use async_gen::{
+    gen, merge,
+    record::Replay,
+    testing::{gate, Stepper},
+    zip, GeneratorState,
+};
+use std::task::Poll;
+
+#[test]
+fn merge_tags_values_and_keeps_returns() {
+    let (ready, opener) = gate();
+    let mut ready = Some(ready);
+    let gens = ['a', 'b'].map(|c| {
+        let ready = ready.take();
+        gen! {
+            if let Some(ready) = ready {
+                ready.await;
+            }
+            yield c;
+            yield c.to_ascii_uppercase();
+            return c as u32;
+        }
+    });
+    let mut g = Stepper::new(merge(gens));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded((1, 'b'))));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded((1, 'B'))));
+    assert_eq!(g.step(), Poll::Pending);
+
+    opener.open();
+    assert!(g.is_woken());
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded((0, 'a'))));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded((0, 'A'))));
+    assert_eq!(
+        g.step(),
+        Poll::Ready(GeneratorState::Complete(vec![97, 98]))
+    );
+}
+
+#[test]
+fn merge_polls_in_turn() {
+    let gens = (0..3).map(|shard| {
+        gen! {
+            for i in 0..2 {
+                yield shard * 10 + i;
+            }
+        }
+    });
+    let mut g = Stepper::new(merge(gens));
+    for expected in [(0, 0), (1, 10), (2, 20), (0, 1), (1, 11), (2, 21)] {
+        assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded(expected)));
+    }
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Complete(vec![(); 3])));
+}
+
+#[test]
+fn merge_nothing() {
+    let mut g = Stepper::new(merge(Vec::<Replay<u8, ()>>::new()));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Complete(vec![])));
+}
+
+#[test]
+fn zip_drains_longer_generator() {
+    let mut g = Stepper::new(zip(
+        gen! {
+            yield 1;
+            return "short";
+        },
+        gen! {
+            for c in ['a', 'b', 'c'] {
+                yield c;
+            }
+            return "long";
+        },
+    ));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded((1, 'a'))));
+    // 'b' is dropped once the short generator completes, then 'c' in the next poll.
+    assert_eq!(g.step(), Poll::Pending);
+    assert!(g.is_woken());
+    assert_eq!(
+        g.step(),
+        Poll::Ready(GeneratorState::Complete(("short", "long")))
+    );
+}
+
+#[test]
+fn zip_with_endless_generator() {
+    let mut g = Stepper::new(zip(
+        gen! {
+            yield 1;
+        },
+        gen! {
+            for i in 0.. {
+                yield i;
+            }
+        },
+    ));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded((1, 0))));
+    // Every poll returns, instead of draining the endless generator.
+    for _ in 0..100 {
+        assert_eq!(g.step(), Poll::Pending);
+        assert!(g.is_woken());
+    }
+}
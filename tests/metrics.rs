@@ -0,0 +1,55 @@
+use async_gen::{gen, AsyncGenerator, GeneratorState, Metered};
+use std::{
+    future::poll_fn,
+    pin::{pin, Pin},
+    time::Duration,
+};
+use tokio::time::sleep;
+
+async fn resume<G: AsyncGenerator>(g: &mut Pin<&mut G>) -> GeneratorState<G::Yield, G::Return> {
+    poll_fn(|cx| g.as_mut().poll_resume(cx)).await
+}
+
+#[tokio::test]
+async fn count_polls_and_yields() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    let mut g = pin!(Metered::new(gen! {
+        while let Some(v) = rx.recv().await {
+            yield v;
+        }
+        return "done";
+    }));
+    tokio::spawn(async move {
+        for i in 0..3 {
+            sleep(Duration::from_millis(5)).await;
+            tx.send(i).await.unwrap();
+        }
+    });
+    for i in 0..3 {
+        assert_eq!(resume(&mut g).await, GeneratorState::Yielded(i));
+    }
+    assert_eq!(resume(&mut g).await, GeneratorState::Complete("done"));
+
+    let stats = g.stats();
+    assert_eq!(stats.yields, 3);
+    assert_eq!(stats.polls, stats.pending_polls + 4);
+    assert!(stats.pending_polls >= 3);
+    assert!(stats.pending >= Duration::from_millis(15));
+}
+
+#[tokio::test]
+async fn slow_consumer() {
+    let mut g = pin!(Metered::new(gen! {
+        yield 1;
+        yield 2;
+    }));
+    assert_eq!(resume(&mut g).await, GeneratorState::Yielded(1));
+    sleep(Duration::from_millis(20)).await;
+    assert_eq!(resume(&mut g).await, GeneratorState::Yielded(2));
+    assert_eq!(resume(&mut g).await, GeneratorState::Complete(()));
+
+    let stats = g.stats();
+    assert_eq!(stats.pending_polls, 0);
+    assert!(stats.suspended >= Duration::from_millis(20));
+    assert!(stats.busy < stats.suspended);
+}
@@ -0,0 +1,61 @@
+use async_gen::{gen, GeneratorState};
+use futures_util::{future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
+use std::{pin::pin, time::Duration};
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn yield_inside_futures_unordered() {
+    let g = gen(|mut c| async move {
+        let mut set = FuturesUnordered::new();
+        set.push(async {
+            for i in 0..3 {
+                c.yield_(i).await;
+            }
+        });
+        while set.next().await.is_some() {}
+        drop(set);
+        c.return_(())
+    });
+    let all = timeout(Duration::from_secs(5), g.collect::<Vec<_>>()).await;
+    assert_eq!(all.unwrap(), [0, 1, 2]);
+}
+
+#[tokio::test]
+async fn yield_next_to_pending_future() {
+    let g = gen(|mut c| async move {
+        let mut set: FuturesUnordered<BoxFuture<'_, u32>> = FuturesUnordered::new();
+        set.push(
+            async {
+                sleep(Duration::from_millis(20)).await;
+                10
+            }
+            .boxed(),
+        );
+        set.push(
+            async {
+                for i in 0..3 {
+                    c.yield_(i).await;
+                }
+                20
+            }
+            .boxed(),
+        );
+        let mut done = Vec::new();
+        while let Some(v) = set.next().await {
+            done.push(v);
+        }
+        drop(set);
+        done.sort();
+        c.return_(done)
+    });
+    let mut g = pin!(g);
+    let mut all = Vec::new();
+    let done = loop {
+        match timeout(Duration::from_secs(5), g.resume()).await.unwrap() {
+            GeneratorState::Yielded(v) => all.push(v),
+            GeneratorState::Complete(done) => break done,
+        }
+    };
+    assert_eq!(all, [0, 1, 2]);
+    assert_eq!(done, [10, 20]);
+}
@@ -0,0 +1,73 @@
+use async_gen::{gen, GeneratorState};
+use std::{
+    pin::pin,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[tokio::test]
+async fn run_ahead() {
+    let produced = AtomicUsize::new(0);
+    let counter = &produced;
+    let mut g = pin!(gen! {
+        for i in 0..5 {
+            counter.fetch_add(1, Ordering::Relaxed);
+            yield i;
+        }
+    }
+    .prefetch(2));
+
+    // The body stays one value ahead of the consumer, on every resume.
+    assert_eq!(g.resume().await, GeneratorState::Yielded(0));
+    assert_eq!(produced.load(Ordering::Relaxed), 2);
+    assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    assert_eq!(produced.load(Ordering::Relaxed), 3);
+    assert_eq!(g.resume().await, GeneratorState::Yielded(2));
+    assert_eq!(produced.load(Ordering::Relaxed), 4);
+    assert_eq!(g.resume().await, GeneratorState::Yielded(3));
+    assert_eq!(produced.load(Ordering::Relaxed), 5);
+    assert_eq!(g.resume().await, GeneratorState::Yielded(4));
+    assert_eq!(g.resume().await, GeneratorState::Complete(()));
+}
+
+#[tokio::test]
+async fn drain_before_return() {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut g = pin!(gen! {
+        let mut count = 0;
+        while let Some(v) = rx.recv().await {
+            count += 1;
+            yield v;
+        }
+        return count;
+    }
+    .prefetch(8));
+
+    for i in 0..3 {
+        tx.send(i).unwrap();
+    }
+    drop(tx);
+    for i in 0..3 {
+        assert_eq!(g.resume().await, GeneratorState::Yielded(i));
+    }
+    assert_eq!(g.resume().await, GeneratorState::Complete(3));
+}
+
+#[tokio::test]
+async fn close_drops_buffered_values() {
+    let mut g = pin!(gen(|mut c| async move {
+        let mut count = 0;
+        for i in 0..3 {
+            count += 1;
+            c.yield_(i).await;
+            if c.is_closing() {
+                break;
+            }
+        }
+        c.return_(count)
+    })
+    .prefetch(2));
+
+    // `1` is buffered, and dropped by `close`.
+    assert_eq!(g.resume().await, GeneratorState::Yielded(0));
+    assert_eq!(g.close().await, 2);
+}
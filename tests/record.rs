@@ -0,0 +1,77 @@
+use async_gen::{
+    gen,
+    record::{Entry, Event, Recorder, Recording},
+    testing::{gate, Stepper},
+    GeneratorState,
+};
+use std::task::Poll;
+
+#[test]
+fn record_pending_gaps() {
+    let (ready, opener) = gate();
+    let mut g = Stepper::new(
+        Recorder::new(gen! {
+            yield 1;
+            ready.await;
+            return "done";
+        })
+        .with_pending(),
+    );
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert_eq!(g.step(), Poll::Pending);
+    opener.open();
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Complete("done")));
+
+    let recording = g.gen().recording().clone();
+    assert!(recording.is_complete());
+    let events: Vec<_> = recording.entries.into_iter().map(|e| e.event).collect();
+    assert_eq!(
+        events,
+        [Event::Yielded(1), Event::Pending, Event::Complete("done")]
+    );
+}
+
+#[test]
+fn replay_recording() {
+    let recording = Recording {
+        entries: vec![
+            Entry {
+                at: None,
+                event: Event::Pending,
+            },
+            Entry {
+                at: None,
+                event: Event::Yielded('a'),
+            },
+            Entry {
+                at: None,
+                event: Event::Complete(7),
+            },
+        ],
+    };
+    let mut g = Stepper::new(recording.replay());
+    assert_eq!(g.step(), Poll::Pending);
+    assert!(g.is_woken());
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded('a')));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Complete(7)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot_round_trip() {
+    let mut g = Stepper::new(
+        Recorder::new(gen! {
+            yield "a".to_string();
+            yield "b".to_string();
+            return 2;
+        })
+        .with_timing(),
+    );
+    while let Poll::Ready(GeneratorState::Yielded(_)) = g.step() {}
+
+    let recording = g.gen().recording().clone();
+    assert!(recording.entries.iter().all(|e| e.at.is_some()));
+    let json = serde_json::to_string(&recording).unwrap();
+    let loaded: Recording<String, i32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded, recording);
+}
@@ -0,0 +1,115 @@
+use async_gen::{
+    gen,
+    testing::{gate, Gate},
+    GeneratorSet, GeneratorState,
+};
+use futures_util::{task::noop_waker, StreamExt};
+use std::{
+    future::{poll_fn, Future},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+#[tokio::test]
+async fn events_are_tagged_with_keys() {
+    let mut set = GeneratorSet::new();
+    for key in 0..3 {
+        set.insert(
+            key,
+            gen! {
+                for i in 0..key {
+                    yield i;
+                }
+                return key * 10;
+            },
+        );
+    }
+    let mut yielded = [0; 3];
+    let mut returned = Vec::new();
+    while let Some((key, state)) = set.next().await {
+        match state {
+            GeneratorState::Yielded(_) => yielded[key] += 1,
+            GeneratorState::Complete(val) => returned.push((key, val)),
+        }
+    }
+    returned.sort();
+    assert_eq!(yielded, [0, 1, 2]);
+    assert_eq!(returned, [(0, 0), (1, 10), (2, 20)]);
+    assert!(set.is_empty());
+}
+
+#[test]
+fn polls_only_woken_generators() {
+    let polls = Arc::new(AtomicUsize::new(0));
+    let mut openers = Vec::new();
+    let mut set = GeneratorSet::new();
+    for key in 0..100 {
+        let (ready, opener) = gate();
+        let polls = polls.clone();
+        let mut ready = Box::pin(ready);
+        set.insert(
+            key,
+            gen! {
+                poll_fn(|cx| {
+                    polls.fetch_add(1, Ordering::Relaxed);
+                    ready.as_mut().poll(cx)
+                })
+                .await;
+                yield key;
+            },
+        );
+        openers.push(opener);
+    }
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert_eq!(set.poll_next_unpin(&mut cx), Poll::Pending);
+    assert_eq!(polls.load(Ordering::Relaxed), 100);
+
+    openers[42].open();
+    let event = set.poll_next_unpin(&mut cx);
+    assert!(matches!(
+        event,
+        Poll::Ready(Some((42, GeneratorState::Yielded(42))))
+    ));
+    assert_eq!(polls.load(Ordering::Relaxed), 101);
+    assert!(matches!(
+        set.poll_next_unpin(&mut cx),
+        Poll::Ready(Some((42, GeneratorState::Complete(()))))
+    ));
+    assert_eq!(set.poll_next_unpin(&mut cx), Poll::Pending);
+    assert_eq!(polls.load(Ordering::Relaxed), 101);
+    assert_eq!(set.len(), 99);
+}
+
+#[test]
+fn insert_and_remove_while_running() {
+    let task = |ready: Option<Gate>, val| {
+        gen! {
+            if let Some(ready) = ready {
+                ready.await;
+            }
+            yield val;
+        }
+    };
+    let (ready, opener) = gate();
+    let mut set = GeneratorSet::new();
+    set.insert("a", task(Some(ready), 1));
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert_eq!(set.poll_next_unpin(&mut cx), Poll::Pending);
+
+    assert!(set.remove(&"a").is_some());
+    assert!(!set.contains_key(&"a"));
+    // Waking a removed generator doesn't poll anything.
+    opener.open();
+    assert_eq!(set.poll_next_unpin(&mut cx), Poll::Ready(None));
+
+    set.insert("b", task(None, 2));
+    assert!(matches!(
+        set.poll_next_unpin(&mut cx),
+        Poll::Ready(Some(("b", GeneratorState::Yielded(2))))
+    ));
+}
@@ -0,0 +1,71 @@
+//! Handoff tests, that also run under Miri:
+//!
+//! cargo +nightly miri test --test soundness
+use async_gen::{gen, gen_throwable, GeneratorState, Return, Yield};
+use futures_util::{task::noop_waker_ref, StreamExt};
+use std::{
+    future::{pending, Future},
+    pin::pin,
+    task::{Context, Poll},
+    thread,
+};
+
+fn poll<F: Future>(fut: std::pin::Pin<&mut F>) -> Poll<F::Output> {
+    fut.poll(&mut Context::from_waker(noop_waker_ref()))
+}
+
+#[test]
+fn handoff_across_threads() {
+    let mut g = Box::pin(
+        gen! {
+            for i in 0..4 {
+                yield i.to_string();
+            }
+        }
+        .prefetch(2),
+    );
+    assert_eq!(poll(pin!(g.next())), Poll::Ready(Some("0".to_string())));
+
+    let rest = thread::spawn(move || {
+        let mut rest = Vec::new();
+        while let Poll::Ready(Some(v)) = poll(pin!(g.next())) {
+            rest.push(v);
+        }
+        rest
+    });
+    assert_eq!(rest.join().unwrap(), ["1", "2", "3"]);
+}
+
+#[test]
+fn throw_and_close_drop_values() {
+    let mut g = pin!(gen_throwable(|mut c| async move {
+        let _ = c.yield_try(Box::new(1)).await;
+        c.yield_many([Box::new(2), Box::new(3)]).await;
+        c.return_(())
+    }));
+    assert_eq!(
+        poll(pin!(g.resume())),
+        Poll::Ready(GeneratorState::Yielded(Box::new(1)))
+    );
+    assert_eq!(
+        poll(pin!(g.throw(Box::new("err")))),
+        Poll::Ready(GeneratorState::Yielded(Box::new(2)))
+    );
+    // The buffered `3` is dropped.
+    assert_eq!(poll(pin!(g.close())), Poll::Ready(()));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "`Yield` is used outside of its generator body")]
+fn escaped_yield_is_rejected() {
+    let mut escaped = None::<Yield<i32>>;
+    let mut g = pin!(gen(|c| {
+        escaped = Some(c);
+        pending::<Return>()
+    }));
+    assert_eq!(poll(pin!(g.resume())), Poll::Pending);
+
+    let mut c = escaped.unwrap();
+    let _ = poll(pin!(c.yield_(1)));
+}
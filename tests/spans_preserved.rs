@@ -8,7 +8,39 @@ async fn spans_preserved() {
      assert_eq!(line!(), 8);
     });
 
+    #[allow(clippy::never_loop)]
     while s.next().await.is_some() {
         unreachable!();
     }
 }
+
+#[tokio::test]
+async fn yield_location() {
+    let line = line!();
+    let mut s = pin!(gen! {
+        yield 1;
+        if true {
+            yield 2;
+        }
+    });
+    assert_eq!(s.last_yield_location(), None);
+
+    s.next().await;
+    let location = s.last_yield_location().unwrap();
+    assert_eq!((location.file(), location.line()), (file!(), line + 2));
+
+    s.next().await;
+    let location = s.last_yield_location().unwrap();
+    assert_eq!((location.file(), location.line()), (file!(), line + 4));
+}
+
+#[tokio::test]
+async fn yield_location_without_macro() {
+    let line = line!();
+    let mut s = pin!(async_gen::gen(|mut c| async move {
+        c.yield_(1).await;
+        c.return_(())
+    }));
+    s.next().await;
+    assert_eq!(s.last_yield_location().unwrap().line(), line + 2);
+}
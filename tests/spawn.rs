@@ -0,0 +1,70 @@
+use async_gen::{gen, GeneratorState};
+use futures_util::StreamExt;
+
+#[tokio::test]
+async fn spawn_with_runtime() {
+    let mut g = gen! {
+        for i in 0..5 {
+            yield i;
+        }
+        return "done";
+    }
+    .spawn_buffered_with(2, |task| {
+        tokio::spawn(task);
+    });
+    for i in 0..5 {
+        assert_eq!(g.resume().await, GeneratorState::Yielded(i));
+    }
+    assert_eq!(g.resume().await, GeneratorState::Complete("done"));
+}
+
+#[tokio::test]
+#[should_panic(expected = "boom")]
+async fn propagate_panic() {
+    let mut g = gen! {
+        yield 1;
+        panic!("boom");
+    }
+    .spawn_buffered_with(1, |task| {
+        tokio::spawn(task);
+    });
+    assert_eq!(g.next().await, Some(1));
+    g.next().await;
+}
+
+#[tokio::test]
+async fn drop_cancels_task() {
+    struct Guard(Option<tokio::sync::oneshot::Sender<()>>);
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            let _ = self.0.take().unwrap().send(());
+        }
+    }
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let mut g = gen! {
+        let _guard = Guard(Some(tx));
+        for i in 0.. {
+            yield i;
+        }
+    }
+    .spawn_buffered_with(1, |task| {
+        tokio::spawn(task);
+    });
+    assert_eq!(g.next().await, Some(0));
+    drop(g);
+    rx.await.unwrap();
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(flavor = "multi_thread")]
+async fn spawn_buffered() {
+    let g = gen! {
+        for i in 0..100 {
+            yield i;
+        }
+    }
+    .spawn_buffered(16);
+    let values: Vec<_> = g.collect().await;
+    assert_eq!(values, (0..100).collect::<Vec<_>>());
+}
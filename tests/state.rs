@@ -0,0 +1,37 @@
+use async_gen::{gen, GeneratorState, State};
+use std::pin::pin;
+
+#[tokio::test]
+async fn state_transitions() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    let mut g = pin!(gen! {
+        while let Some(v) = rx.recv().await {
+            yield v;
+        }
+    });
+    assert_eq!(g.state(), State::NotStarted);
+
+    tx.send(1).await.unwrap();
+    assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    assert_eq!(g.state(), State::SuspendedAtYield);
+
+    let waker = futures_util::task::noop_waker();
+    let mut cx = std::task::Context::from_waker(&waker);
+    assert!(g.as_mut().poll_resume(&mut cx).is_pending());
+    assert_eq!(g.state(), State::Pending);
+
+    drop(tx);
+    assert_eq!(g.resume().await, GeneratorState::Complete(()));
+    assert_eq!(g.state(), State::Complete);
+}
+
+#[test]
+fn debug_shows_state() {
+    let g = gen! {
+        yield 1;
+    };
+    assert_eq!(
+        format!("{g:?}"),
+        "AsyncGen { state: NotStarted, last_yield_location: None, .. }"
+    );
+}
@@ -0,0 +1,60 @@
+use async_gen::{gen, GeneratorState};
+use std::pin::pin;
+
+#[tokio::test]
+async fn stop_requested() {
+    let mut g = pin!(gen(|mut c| async move {
+        let mut sum = 0;
+        for i in 1.. {
+            if c.stop_requested() {
+                break;
+            }
+            sum += i;
+            c.yield_(i).await;
+        }
+        c.return_(sum)
+    }));
+    assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+    assert_eq!(g.resume().await, GeneratorState::Yielded(2));
+    g.request_stop();
+    assert_eq!(g.resume().await, GeneratorState::Complete(3));
+}
+
+#[tokio::test]
+async fn stop_from_another_task() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    let mut g = pin!(gen(|mut c| async move {
+        let mut flushed = Vec::new();
+        while let Some(v) = rx.recv().await {
+            if c.stop_requested() {
+                flushed.push(v);
+                break;
+            }
+            c.yield_(v).await;
+        }
+        c.return_(flushed)
+    }));
+
+    let stop = g.stop_handle();
+    tx.send(1).await.unwrap();
+    assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+
+    tokio::spawn(async move {
+        stop.request_stop();
+        tx.send(2).await.unwrap();
+    })
+    .await
+    .unwrap();
+    assert_eq!(g.resume().await, GeneratorState::Complete(vec![2]));
+}
+
+#[tokio::test]
+async fn stop_handle_after_request() {
+    let mut g = pin!(gen! {
+        yield 1;
+    });
+    g.request_stop();
+    let stop = g.stop_handle();
+    assert!(stop.is_stop_requested());
+    assert_eq!(g.resume().await, GeneratorState::Yielded(1));
+}
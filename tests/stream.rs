@@ -84,7 +84,7 @@ async fn borrow_self() {
     struct Data(String);
 
     impl Data {
-        fn stream<'a>(&'a self) -> impl Stream<Item = &str> + 'a {
+        fn stream<'a>(&'a self) -> impl Stream<Item = &'a str> + 'a {
             gen! {
                 yield &self.0[..];
             }
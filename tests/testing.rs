@@ -0,0 +1,50 @@
+use async_gen::{
+    assert_yields, gen,
+    testing::{gate, Stepper},
+    GeneratorState,
+};
+use std::task::Poll;
+
+#[test]
+fn yields_in_order() {
+    assert_yields!(gen! {}, []);
+    assert_yields!(
+        gen! {
+            for i in 0..3 {
+                yield i * 2;
+            }
+            return i32::MAX;
+        },
+        [0, 2, 4],
+        returns = i32::MAX
+    );
+}
+
+#[test]
+#[should_panic(expected = "unexpected value yielded at index 1")]
+fn mismatched_value() {
+    assert_yields!(gen! { yield 1; yield 3; }, [1, 2]);
+}
+
+#[test]
+fn scripted_pendings() {
+    let (first, open_first) = gate();
+    let (second, open_second) = gate();
+    let mut g = Stepper::new(gen! {
+        first.await;
+        yield 1;
+        second.await;
+        yield 2;
+    });
+
+    assert_eq!(g.step(), Poll::Pending);
+    open_second.open();
+    assert!(!g.is_woken());
+    assert_eq!(g.step(), Poll::Pending);
+
+    open_first.open();
+    assert!(g.is_woken());
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded(2)));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Complete(())));
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "tracing")]
+
+use async_gen::{gen, testing::Stepper, GeneratorState};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+/// `(span, message, yields)` of an event.
+type Record = (Option<String>, String, Option<u64>);
+
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<Record>>>,
+    spans: Arc<Mutex<Vec<String>>>,
+    current: Arc<Mutex<Vec<u64>>>,
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    yields: Option<u64>,
+}
+
+impl Visit for Fields {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == "yields" {
+            self.yields = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span.metadata().name().to_string());
+        span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let span = self.current.lock().unwrap().last().map(|id| {
+            let spans = self.spans.lock().unwrap();
+            spans[*id as usize - 1].clone()
+        });
+        let mut events = self.events.lock().unwrap();
+        events.push((span, fields.message, fields.yields));
+    }
+
+    fn enter(&self, span: &span::Id) {
+        self.current.lock().unwrap().push(span.into_u64());
+    }
+
+    fn exit(&self, _: &span::Id) {
+        self.current.lock().unwrap().pop();
+    }
+}
+
+fn event(span: &str, message: &str, yields: Option<u64>) -> Record {
+    (Some(span.to_string()), message.to_string(), yields)
+}
+
+#[test]
+fn events_per_resumption() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+
+    let mut g = Stepper::new(
+        gen! {
+            tracing::info!("body");
+            yield 1;
+            yield 2;
+            return "done";
+        }
+        .instrument(tracing::info_span!("numbers")),
+    );
+
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded(2)));
+    assert_eq!(g.step(), Poll::Ready(GeneratorState::Complete("done")));
+
+    assert_eq!(
+        *recorder.events.lock().unwrap(),
+        [
+            event("numbers", "body", None),
+            event("numbers", "generator yielded", Some(1)),
+            event("numbers", "generator yielded", Some(2)),
+            event("numbers", "generator completed", Some(2)),
+        ]
+    );
+}
+
+#[test]
+fn drop_before_complete() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+    {
+        let mut g = Stepper::new(
+            gen! {
+                yield 1;
+                yield 2;
+            }
+            .instrument(tracing::info_span!("numbers")),
+        );
+        assert_eq!(g.step(), Poll::Ready(GeneratorState::Yielded(1)));
+    }
+    assert_eq!(
+        recorder.events.lock().unwrap().last(),
+        Some(&event(
+            "numbers",
+            "generator dropped before completion",
+            Some(1)
+        ))
+    );
+}
@@ -1,4 +1,4 @@
-use async_gen::{gen, GeneratorState};
+use async_gen::{gen, gen_throwable, GeneratorState};
 use std::pin::pin;
 
 #[tokio::test]
@@ -24,3 +24,46 @@ async fn yield_then_err() {
     assert_eq!(s.resume().await, GeneratorState::Yielded("hello"));
     assert_eq!(s.resume().await, GeneratorState::Complete(Err("world")));
 }
+
+#[tokio::test]
+async fn throw_into_yield() {
+    let mut s = pin!(gen! {
+        yield? "hello";
+        yield? "world";
+        Result::<_, &str>::Ok(())
+    });
+    assert_eq!(s.resume().await, GeneratorState::Yielded("hello"));
+    assert_eq!(s.throw("oops").await, GeneratorState::Complete(Err("oops")));
+}
+
+#[tokio::test]
+async fn yield_try_without_throw() {
+    let mut s = pin!(gen! {
+        yield? 1;
+        yield? 2;
+        Ok::<(), std::io::Error>(())
+    });
+    assert!(matches!(s.resume().await, GeneratorState::Yielded(1)));
+    assert!(matches!(s.resume().await, GeneratorState::Yielded(2)));
+    assert!(matches!(s.resume().await, GeneratorState::Complete(Ok(()))));
+}
+
+#[tokio::test]
+async fn recover_from_thrown_error() {
+    let mut s = pin!(gen_throwable(|mut c| async move {
+        let mut errors = vec![];
+        for i in 0..3 {
+            if let Err(err) = c.yield_try(i).await {
+                errors.push(err);
+            }
+        }
+        c.return_(errors)
+    }));
+    assert_eq!(s.resume().await, GeneratorState::Yielded(0));
+    assert_eq!(s.throw("first").await, GeneratorState::Yielded(1));
+    assert_eq!(s.throw("second").await, GeneratorState::Yielded(2));
+    assert_eq!(
+        s.resume().await,
+        GeneratorState::Complete(vec!["first", "second"])
+    );
+}
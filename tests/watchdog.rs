@@ -0,0 +1,113 @@
+use async_gen::{
+    gen,
+    watchdog::{Clock, Stall, Thresholds, Watchdog},
+    AsyncGenerator, GeneratorState,
+};
+use futures_util::task::noop_waker_ref;
+use std::{
+    pin::pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+#[derive(Clone)]
+struct MockClock {
+    start: Instant,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl MockClock {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Arc::default(),
+        }
+    }
+
+    fn advance(&self, secs: u64) {
+        *self.elapsed.lock().unwrap() += Duration::from_secs(secs);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+}
+
+#[test]
+fn consumer_stall() {
+    let clock = MockClock::new();
+    let stalls = Mutex::new(vec![]);
+    let thresholds = Thresholds::new(Duration::from_secs(5));
+    let mut g = pin!(Watchdog::with_clock(
+        gen! {
+            yield 1;
+            yield 2;
+        },
+        thresholds,
+        clock.clone(),
+        |stall| stalls.lock().unwrap().push(stall),
+    ));
+    let mut cx = Context::from_waker(noop_waker_ref());
+
+    assert_eq!(
+        g.as_mut().poll_resume(&mut cx),
+        Poll::Ready(GeneratorState::Yielded(1))
+    );
+    clock.advance(3);
+    assert_eq!(
+        g.as_mut().poll_resume(&mut cx),
+        Poll::Ready(GeneratorState::Yielded(2))
+    );
+    assert!(stalls.lock().unwrap().is_empty());
+
+    clock.advance(6);
+    let handle = g.handle();
+    handle.check();
+    clock.advance(1);
+    handle.check();
+    assert_eq!(
+        g.as_mut().poll_resume(&mut cx),
+        Poll::Ready(GeneratorState::Complete(()))
+    );
+    assert_eq!(
+        *stalls.lock().unwrap(),
+        [Stall::Consumer(Duration::from_secs(6))]
+    );
+}
+
+#[test]
+fn pending_stall() {
+    let clock = MockClock::new();
+    let stalls = Mutex::new(vec![]);
+    let thresholds = Thresholds {
+        consumer: Duration::MAX,
+        pending: Duration::from_secs(5),
+    };
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut g = pin!(Watchdog::with_clock(
+        gen! {
+            while let Some(v) = rx.recv().await {
+                yield v;
+            }
+        },
+        thresholds,
+        clock.clone(),
+        |stall| stalls.lock().unwrap().push(stall),
+    ));
+    let mut cx = Context::from_waker(noop_waker_ref());
+
+    assert_eq!(g.as_mut().poll_resume(&mut cx), Poll::Pending);
+    clock.advance(10);
+    tx.send(1).unwrap();
+    assert_eq!(
+        g.as_mut().poll_resume(&mut cx),
+        Poll::Ready(GeneratorState::Yielded(1))
+    );
+    assert_eq!(
+        *stalls.lock().unwrap(),
+        [Stall::Pending(Duration::from_secs(10))]
+    );
+}